@@ -0,0 +1,173 @@
+//! A built-in AI that can steer any snake.
+//!
+//! Each tick it looks for the shortest path to the food with a breadth-first
+//! search. It only takes that path if, once the food is eaten, the snake could
+//! still reach its own tail, since a snake that can follow its tail can never
+//! be trapped. Otherwise it picks the safe move that keeps the tail in reach
+//! and the most room to move, which makes it chase its tail until the food is
+//! safe to go for. Poison is never a target: it steers around it and waits
+//! for it to move.
+//!
+//! The searches know that the snakes move: a body segment `k` cells from its
+//! tail is gone after `k + 1` moves, or later still while the snake is
+//! growing, so a path may run through it if it gets there late enough.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::game::{Direction, FoodKind, GameState, Point};
+
+/// Picks the direction `player`'s snake should turn to for the next step, or
+/// `None` if it is dead.
+///
+/// The result is never a reversal, so it can be passed straight to
+/// [`GameState::turn`].
+pub fn choose_direction(game: &GameState, player: usize) -> Option<Direction> {
+    let snake = game.snakes.get(player).filter(|snake| snake.is_alive())?;
+    let head = snake.head();
+    let search = Search::new(game, player, &snake.body, snake.growing);
+
+    // Cells another snake's head could move into next, where we'd crash head-on
+    let risky: HashSet<Point> = game
+        .snakes
+        .iter()
+        .enumerate()
+        .filter(|&(other, rival)| other != player && rival.is_alive())
+        .flat_map(|(_, rival)| Direction::ALL.into_iter().filter_map(|d| game.next_cell(rival.head(), d)))
+        .collect();
+
+    let moves: Vec<(Direction, Point)> = Direction::ALL
+        .into_iter()
+        .filter(|&d| d != snake.direction.opposite())
+        .filter_map(|d| game.next_cell(head, d).map(|cell| (d, cell)))
+        .filter(|&(_, cell)| search.can_enter(cell, 1))
+        .collect();
+
+    let poison = (game.food_kind == FoodKind::Poison).then_some(game.food);
+
+    // 1) Go for the food if the snake can still reach its tail afterwards
+    if let Some(path) = search.path(head, game.food).filter(|_| poison.is_none()) {
+        let first = path[0];
+        if !risky.contains(&first) && tail_reachable_after(game, player, &path, true) {
+            if let Some(&(direction, _)) = moves.iter().find(|&&(_, cell)| cell == first) {
+                return Some(direction);
+            }
+        }
+    }
+
+    // 2) Otherwise make the safest move: avoid head-ons, keep the tail in
+    // reach, stay off poison, and keep as much room as possible
+    moves
+        .iter()
+        .max_by_key(|&&(_, cell)| {
+            let grows = cell == game.food && poison.is_none();
+            let tail = tail_reachable_after(game, player, &[cell], grows);
+            (!risky.contains(&cell), tail, Some(cell) != poison, search.area(cell))
+        })
+        .map(|&(direction, _)| direction)
+        // Boxed in: carry on and hope
+        .or(Some(snake.direction))
+}
+
+/// Whether `player`'s snake could reach its tail after following `path`,
+/// eating at the end of it if `eats`.
+fn tail_reachable_after(game: &GameState, player: usize, path: &[Point], eats: bool) -> bool {
+    let snake = &game.snakes[player];
+    let mut body = snake.body.clone();
+    let mut growing = snake.growing;
+    for (i, &cell) in path.iter().enumerate() {
+        body.push_back(cell);
+        if eats && i == path.len() - 1 {
+            // The first new segment is this one; the rest come on later moves
+            growing += game.food_kind.growth().max(1) as u32 - 1;
+        } else if growing > 0 {
+            growing -= 1;
+        } else {
+            body.pop_front();
+        }
+    }
+    let (Some(&head), Some(&tail)) = (body.back(), body.front()) else {
+        return false;
+    };
+    // A snake of one or two cells always has room to follow itself, unless it
+    // is still growing
+    (body.len() <= 2 && growing == 0) || Search::new(game, player, &body, growing).path(head, tail).is_some()
+}
+
+/// Breadth-first searches over the board, with `body` standing in for the
+/// snake of `player`, which still has `growing` segments to add.
+struct Search<'a> {
+    game: &'a GameState,
+    /// The move on which each occupied cell can first be entered.
+    free_on: HashMap<Point, usize>,
+}
+
+impl<'a> Search<'a> {
+    fn new(game: &'a GameState, player: usize, body: &VecDeque<Point>, growing: u32) -> Self {
+        let mut free_on = HashMap::new();
+        let others = game
+            .snakes
+            .iter()
+            .enumerate()
+            .filter(|&(other, _)| other != player)
+            .map(|(_, snake)| (&snake.body, snake.growing));
+        // A growing snake's tail stays put for as many moves as it has to grow
+        for (segments, growing) in others.chain(std::iter::once((body, growing))) {
+            for (k, &cell) in segments.iter().enumerate() {
+                free_on.insert(cell, k + 2 + growing as usize);
+            }
+        }
+        Search { game, free_on }
+    }
+
+    /// Whether a head can move into `cell` on move number `step` (from 1).
+    fn can_enter(&self, cell: Point, step: usize) -> bool {
+        !self.game.obstacles.contains(&cell) && self.free_on.get(&cell).is_none_or(|&free| step >= free)
+    }
+
+    /// The cells of the shortest path from `from` to `to`, excluding `from`.
+    fn path(&self, from: Point, to: Point) -> Option<Vec<Point>> {
+        let mut came_from = HashMap::from([(from, from)]);
+        let mut queue = VecDeque::from([(from, 0)]);
+        while let Some((cell, distance)) = queue.pop_front() {
+            for direction in Direction::ALL {
+                let Some(next) = self.game.next_cell(cell, direction) else {
+                    continue;
+                };
+                if came_from.contains_key(&next) || !self.can_enter(next, distance + 1) {
+                    continue;
+                }
+                came_from.insert(next, cell);
+                if next == to {
+                    // Walk back to the start
+                    let mut path = vec![next];
+                    let mut current = cell;
+                    while current != from {
+                        path.push(current);
+                        current = came_from[&current];
+                    }
+                    path.reverse();
+                    return Some(path);
+                }
+                queue.push_back((next, distance + 1));
+            }
+        }
+        None
+    }
+
+    /// How many cells can be reached from `from`, which has just been entered.
+    fn area(&self, from: Point) -> usize {
+        let mut seen = HashSet::from([from]);
+        let mut queue = VecDeque::from([(from, 1)]);
+        while let Some((cell, distance)) = queue.pop_front() {
+            for direction in Direction::ALL {
+                if let Some(next) = self.game.next_cell(cell, direction) {
+                    if !seen.contains(&next) && self.can_enter(next, distance + 1) {
+                        seen.insert(next);
+                        queue.push_back((next, distance + 1));
+                    }
+                }
+            }
+        }
+        seen.len()
+    }
+}
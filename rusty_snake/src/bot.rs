@@ -0,0 +1,250 @@
+//! Bots written in any language, run as a subprocess and spoken to over
+//! stdin and stdout in JSON lines.
+//!
+//! Before every step the game writes the board to the bot's stdin as one line
+//! of JSON:
+//!
+//! ```text
+//! {"tick":12,"width":40,"height":20,"walls":"solid","food":{"x":15,"y":4},
+//!  "food_kind":"regular","obstacles":[],"you":0,"snakes":[{"head":{"x":9,"y":10},
+//!  "body":[{"x":7,"y":10},{"x":8,"y":10},{"x":9,"y":10}],"direction":"right",
+//!  "score":2,"alive":true}]}
+//! ```
+//!
+//! (shown wrapped here, but always sent on a single line). `you` is the bot's
+//! index into `snakes`, and each `body` runs from the tail to the head.
+//! `food_kind` is `regular`, `bonus`, `golden` or `poison`.
+//! Coordinates count from the top-left corner, walls included, so the
+//! playfield is `1..width - 1` by `1..height - 1`.
+//!
+//! The bot answers with one line holding a direction as a JSON string, such as
+//! `"up"`; the quotes may be left off. A bot that answers late, answers
+//! something else, or exits forfeits the game. When the game ends the bot's
+//! stdin is closed.
+
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::controller::{SnakeController, View};
+use crate::game::{Direction, FoodKind, Point, WallMode};
+
+/// How long a bot gets to answer each move, unless told otherwise.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Extra time for the first move, while the bot starts up.
+pub const STARTUP_TIME: Duration = Duration::from_secs(2);
+
+#[derive(Debug)]
+pub enum BotError {
+    /// The command could not be started.
+    Spawn(io::Error),
+    Io(io::Error),
+    /// No answer within the per-move timeout.
+    TimedOut(Duration),
+    /// The bot closed its stdout or exited.
+    Exited,
+    /// The answer was not a direction.
+    InvalidReply(String),
+}
+
+impl fmt::Display for BotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BotError::Spawn(err) => write!(f, "could not start the bot: {}", err),
+            BotError::Io(err) => write!(f, "{}", err),
+            BotError::TimedOut(timeout) => write!(f, "no move within {}ms", timeout.as_millis()),
+            BotError::Exited => write!(f, "the bot exited"),
+            BotError::InvalidReply(reply) => write!(f, "`{}` is not a direction", reply),
+        }
+    }
+}
+
+impl std::error::Error for BotError {}
+
+impl From<io::Error> for BotError {
+    fn from(err: io::Error) -> Self {
+        BotError::Io(err)
+    }
+}
+
+/// The board as sent to a bot.
+#[derive(Debug, Serialize)]
+struct BoardMessage {
+    tick: u64,
+    width: i32,
+    height: i32,
+    walls: WallMode,
+    food: Point,
+    food_kind: FoodKind,
+    obstacles: Vec<Point>,
+    you: usize,
+    snakes: Vec<SnakeMessage>,
+}
+
+#[derive(Debug, Serialize)]
+struct SnakeMessage {
+    head: Point,
+    body: Vec<Point>,
+    direction: Direction,
+    score: i32,
+    alive: bool,
+}
+
+impl BoardMessage {
+    fn new(view: &View) -> Self {
+        // Sorted, so the same board always gives the same line
+        let mut obstacles: Vec<Point> = view.obstacles().iter().copied().collect();
+        obstacles.sort_by_key(|p| (p.y, p.x));
+        BoardMessage {
+            tick: view.tick(),
+            width: view.width(),
+            height: view.height(),
+            walls: view.walls(),
+            food: view.food(),
+            food_kind: view.food_kind(),
+            obstacles,
+            you: view.player(),
+            snakes: view
+                .snakes()
+                .iter()
+                .map(|snake| SnakeMessage {
+                    head: snake.head(),
+                    body: snake.body.iter().copied().collect(),
+                    direction: snake.direction,
+                    score: snake.score,
+                    alive: snake.is_alive(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Reads a bot's answer: a JSON string such as `"up"`, or a bare word.
+pub fn parse_reply(reply: &str) -> Result<Direction, BotError> {
+    let reply = reply.trim();
+    serde_json::from_str(reply)
+        .or_else(|_| serde_json::from_str(&format!("\"{}\"", reply.to_ascii_lowercase())))
+        .map_err(|_| BotError::InvalidReply(reply.to_string()))
+}
+
+/// Steers a snake by asking a bot subprocess.
+///
+/// If the bot misbehaves it is stopped and the controller forfeits, keeping
+/// the reason for [`BotController::forfeit_reason`].
+#[derive(Debug)]
+pub struct BotController {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    replies: Receiver<io::Result<String>>,
+    timeout: Duration,
+    moves: u64,
+    forfeit: Option<BotError>,
+}
+
+impl BotController {
+    /// Starts `command`, split on whitespace into the program and its
+    /// arguments (no shell is involved). The bot's stderr is discarded.
+    pub fn spawn(command: &str, timeout: Duration) -> Result<Self, BotError> {
+        let mut words = command.split_whitespace();
+        let program = words
+            .next()
+            .ok_or_else(|| BotError::Spawn(io::Error::new(io::ErrorKind::InvalidInput, "empty command")))?;
+        let mut child = Command::new(program)
+            .args(words)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(BotError::Spawn)?;
+
+        // Read answers on a thread, so a bot that hangs can be timed out
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let (sender, replies) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(BotController {
+            stdin: child.stdin.take(),
+            child,
+            replies,
+            timeout,
+            moves: 0,
+            forfeit: None,
+        })
+    }
+
+    /// Why the bot forfeited, if it has.
+    pub fn forfeit_reason(&self) -> Option<&BotError> {
+        self.forfeit.as_ref()
+    }
+
+    /// Sends the board and waits for the bot's move.
+    fn ask(&mut self, view: &View) -> Result<Direction, BotError> {
+        let line = serde_json::to_string(&BoardMessage::new(view)).map_err(io::Error::from)?;
+        let stdin = self.stdin.as_mut().ok_or(BotError::Exited)?;
+        writeln!(stdin, "{}", line)
+            .and_then(|_| stdin.flush())
+            .map_err(|err| match err.kind() {
+                io::ErrorKind::BrokenPipe => BotError::Exited,
+                _ => BotError::Io(err),
+            })?;
+
+        let timeout = if self.moves == 0 { self.timeout + STARTUP_TIME } else { self.timeout };
+        self.moves += 1;
+        match self.replies.recv_timeout(timeout) {
+            Ok(Ok(reply)) => parse_reply(&reply),
+            Ok(Err(err)) => Err(BotError::Io(err)),
+            Err(RecvTimeoutError::Timeout) => Err(BotError::TimedOut(self.timeout)),
+            Err(RecvTimeoutError::Disconnected) => Err(BotError::Exited),
+        }
+    }
+
+    /// Closes the bot's stdin and gives it a moment to exit before killing it.
+    fn stop(&mut self) {
+        self.stdin = None;
+        for _ in 0..10 {
+            if let Ok(Some(_)) = self.child.try_wait() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+impl SnakeController for BotController {
+    fn next_direction(&mut self, view: &View) -> Direction {
+        if self.forfeit.is_none() {
+            match self.ask(view) {
+                Ok(direction) => return direction,
+                Err(err) => {
+                    self.forfeit = Some(err);
+                    self.stop();
+                }
+            }
+        }
+        view.snake().direction
+    }
+
+    fn has_forfeited(&self) -> bool {
+        self.forfeit.is_some()
+    }
+}
+
+impl Drop for BotController {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
@@ -0,0 +1,112 @@
+//! Campaign mode: play through the bundled levels in order.
+//!
+//! Reaching a level's target score moves on to the next map. Dying, or
+//! quitting mid-level, costs a life and restarts the level; the run ends when
+//! the lives run out. Progress is saved between levels so a run can be
+//! resumed later.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::level::Level;
+
+/// Lives at the start of a campaign.
+pub const STARTING_LIVES: u32 = 3;
+
+/// Target score for levels whose map does not set one.
+pub const DEFAULT_TARGET: i32 = 10;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Campaign {
+    /// Index of the current level among the bundled levels.
+    pub level: usize,
+    pub lives: u32,
+    /// Sum of the scores of the completed levels.
+    pub total_score: i32,
+}
+
+impl Default for Campaign {
+    fn default() -> Self {
+        Campaign {
+            level: 0,
+            lives: STARTING_LIVES,
+            total_score: 0,
+        }
+    }
+}
+
+impl Campaign {
+    pub fn new() -> Self {
+        Campaign::default()
+    }
+
+    /// How many levels the campaign has.
+    pub fn level_count() -> usize {
+        Level::bundled_names().count()
+    }
+
+    /// The level being played.
+    pub fn current_level(&self) -> Level {
+        let name = Level::bundled_names()
+            .nth(self.level)
+            .expect("campaign level is in range");
+        Level::bundled(name).expect("bundled levels are valid")
+    }
+
+    /// The score needed to complete the current level.
+    pub fn target(&self) -> i32 {
+        self.current_level().target.unwrap_or(DEFAULT_TARGET)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.level >= Campaign::level_count()
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.lives == 0
+    }
+
+    /// Banks the level's score and moves on. Returns `false` once the last
+    /// level has been completed.
+    pub fn complete_level(&mut self, score: i32) -> bool {
+        self.total_score += score;
+        self.level += 1;
+        !self.is_finished()
+    }
+
+    /// Takes a life. Returns `false` once none are left.
+    pub fn lose_life(&mut self) -> bool {
+        self.lives = self.lives.saturating_sub(1);
+        !self.is_over()
+    }
+
+    /// The default location of the saved progress, under the user's data directory.
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("rusty_snake").join("campaign.json"))
+    }
+
+    /// Loads saved progress. A missing, corrupt or finished save gives `None`.
+    pub fn load(path: &Path) -> Option<Campaign> {
+        let contents = fs::read_to_string(path).ok()?;
+        let campaign: Campaign = serde_json::from_str(&contents).ok()?;
+        (!campaign.is_finished() && !campaign.is_over()).then_some(campaign)
+    }
+
+    /// Writes the progress to `path`, creating its directory if needed.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+
+    /// Removes saved progress once a run has ended.
+    pub fn clear(path: &Path) -> io::Result<()> {
+        match fs::remove_file(path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,244 @@
+use clap::{CommandFactory, Parser, Subcommand};
+use rusty_snake::bot;
+use rusty_snake::game::{Difficulty, FoodMix, Settings, WallMode, MAX_BOARD_SIZE, MAX_SPEED, MIN_BOARD_SIZE, MIN_SPEED};
+use rusty_snake::level::Level;
+use rusty_snake::net;
+use rusty_snake::profile::Profile;
+use rusty_snake::tournament::Format;
+use std::path::PathBuf;
+
+/// Command-line options for the terminal game.
+///
+/// Any setting given here skips the matching question in the settings menu.
+#[derive(Debug, Parser)]
+#[command(version, about = "A classic snake game for the terminal", long_about = None)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Board width, walls included
+    #[arg(long, requires = "height", value_parser = clap::value_parser!(i32).range(i64::from(MIN_BOARD_SIZE)..=i64::from(MAX_BOARD_SIZE)))]
+    pub width: Option<i32>,
+
+    /// Board height, walls included
+    #[arg(long, requires = "width", value_parser = clap::value_parser!(i32).range(i64::from(MIN_BOARD_SIZE)..=i64::from(MAX_BOARD_SIZE)))]
+    pub height: Option<i32>,
+
+    /// Play on a bundled level (e.g. pillars) or a level map file
+    #[arg(long, value_name = "NAME|FILE", conflicts_with_all = ["width", "height"], value_parser = parse_level)]
+    pub level: Option<Level>,
+
+    /// Play the campaign through all bundled levels, resuming saved progress
+    #[arg(long, conflicts_with_all = ["width", "height", "level"])]
+    pub campaign: bool,
+
+    /// Number of players: 1, or 2 sharing the keyboard (arrows vs WASD)
+    #[arg(long, conflicts_with = "campaign", value_parser = clap::value_parser!(u8).range(1..=2))]
+    pub players: Option<u8>,
+
+    /// Walls: solid, or wrap to come back in on the opposite edge
+    #[arg(long, value_parser = parse_wall_mode)]
+    pub walls: Option<WallMode>,
+
+    /// Milliseconds per tick; small boards have a higher minimum
+    #[arg(long, conflicts_with = "mode", value_parser = clap::value_parser!(u64).range(MIN_SPEED..=MAX_SPEED))]
+    pub speed: Option<u64>,
+
+    /// Difficulty preset: easy, normal or hard
+    #[arg(long, value_parser = parse_difficulty)]
+    pub mode: Option<Difficulty>,
+
+    /// Speed up as the score grows: arcade, marathon or sprint
+    #[arg(long, value_name = "NAME", conflicts_with_all = ["speed", "mode"], value_parser = parse_profile)]
+    pub profile: Option<Profile>,
+
+    /// Food: classic (regular food only) or mixed (with bonus, golden and poison food)
+    #[arg(long, value_name = "MIX", value_parser = parse_food_mix)]
+    pub food: Option<FoodMix>,
+
+    /// Seed for food placement; the same seed replays the same game
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Let the built-in AI steer player 1 (the autopilot key, Tab by default,
+    /// toggles it in game)
+    #[arg(long)]
+    pub autopilot: bool,
+
+    /// Let an external bot steer player 1: a command that reads the board as
+    /// JSON lines on stdin and answers each with a direction
+    #[arg(long, value_name = "COMMAND", conflicts_with = "autopilot")]
+    pub bot: Option<String>,
+
+    /// Milliseconds the bot gets to answer each move before it forfeits
+    #[arg(long, value_name = "MS", requires = "bot", default_value_t = bot::DEFAULT_TIMEOUT.as_millis() as u64)]
+    pub bot_timeout: u64,
+
+    /// Color theme: classic, high-contrast, mono, colorblind, okabe-ito, or
+    /// one from your themes.json
+    #[arg(long, value_name = "NAME")]
+    pub theme: Option<String>,
+
+    /// Draw with ASCII characters only, for terminals without Unicode block characters
+    #[arg(long)]
+    pub ascii: bool,
+
+    /// Key preset: arrows, wasd or vim, changed by any bindings in your keys.json
+    #[arg(long, value_name = "PRESET")]
+    pub keys: Option<String>,
+
+    /// Skip the splash screen
+    #[arg(long)]
+    pub no_splash: bool,
+
+    /// Play back a saved replay instead of starting a game
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["width", "height", "level", "campaign", "players", "walls", "speed", "mode", "profile", "food", "seed", "autopilot", "bot"])]
+    pub replay: Option<PathBuf>,
+}
+
+/// Ways to play other than a local game.
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Host a two-player game over the network and wait for someone to join
+    Host {
+        /// Port to listen on
+        #[arg(long, default_value_t = net::DEFAULT_PORT)]
+        port: u16,
+    },
+    /// Join a game started with `host`, as player 2
+    Join {
+        /// The host's address, e.g. 192.168.1.20:7878 (the port defaults to 7878)
+        addr: String,
+    },
+    /// Play seeded games with each bot, without drawing anything, and compare
+    /// their results. The board comes from the options before `tournament`
+    Tournament {
+        /// A bot to enter: `autopilot` for the built-in AI, or a command as
+        /// for `--bot`. Repeat to enter several
+        #[arg(long = "bot", value_name = "autopilot|COMMAND", required = true)]
+        bots: Vec<String>,
+
+        /// Games per bot; game i uses seed `--seed` + i
+        #[arg(long, default_value_t = 100)]
+        games: u64,
+
+        /// Stop a game that is still going after this many ticks
+        #[arg(long, default_value_t = 10_000)]
+        max_ticks: u64,
+
+        /// Milliseconds a bot gets to answer each move before it forfeits
+        #[arg(long, value_name = "MS", default_value_t = bot::DEFAULT_TIMEOUT.as_millis() as u64)]
+        bot_timeout: u64,
+
+        /// Games to run at once (defaults to the number of CPUs)
+        #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+        jobs: Option<u64>,
+
+        /// Output: table, csv or json
+        #[arg(long, default_value = "table", value_parser = parse_format)]
+        format: Format,
+    },
+}
+
+impl Cli {
+    /// Exits with a usage error if an option doesn't apply to the subcommand.
+    ///
+    /// The settings of a network game come from the host, and it always has
+    /// two players. Tournament games are single-player and have no clock.
+    pub fn check_command(&self) {
+        let conflict = match &self.command {
+            None => None,
+            Some(_) if self.autopilot || self.bot.is_some() => Some("the autopilot and bots only play local games"),
+            Some(Command::Host { .. }) if self.campaign => Some("the campaign can't be hosted; it is single-player"),
+            Some(Command::Host { .. }) if self.players == Some(1) => Some("a hosted game always has two players"),
+            Some(Command::Host { .. }) if self.replay.is_some() => Some("`--replay` can't be used with `host`"),
+            Some(Command::Host { .. }) => None,
+            Some(Command::Join { .. }) => {
+                let game_options = self.board_given()
+                    || self.players.is_some()
+                    || self.walls.is_some()
+                    || self.initial_speed().is_some()
+                    || self.food.is_some()
+                    || self.seed.is_some()
+                    || self.replay.is_some();
+                game_options.then_some("the host chooses the settings; `join` only takes an address")
+            }
+            Some(Command::Tournament { .. }) if self.campaign => Some("the campaign can't be played in a tournament"),
+            Some(Command::Tournament { .. }) if self.players == Some(2) => Some("tournament games are single-player"),
+            Some(Command::Tournament { .. }) if self.replay.is_some() => Some("`--replay` can't be used with `tournament`"),
+            Some(Command::Tournament { .. }) => None,
+        };
+        if let Some(message) = conflict {
+            Cli::command()
+                .error(clap::error::ErrorKind::ArgumentConflict, message)
+                .exit();
+        }
+    }
+
+    /// The board size, if both `--width` and `--height` were given.
+    pub fn board_size(&self) -> Option<(i32, i32)> {
+        self.width.zip(self.height)
+    }
+
+    /// Whether the board was chosen on the command line, by size, level or campaign.
+    pub fn board_given(&self) -> bool {
+        self.board_size().is_some() || self.level.is_some() || self.campaign
+    }
+
+    /// The starting speed from `--speed`, `--mode` or `--profile`.
+    pub fn initial_speed(&self) -> Option<u64> {
+        let profile = self.profile.as_ref().map(|profile| profile.start);
+        self.speed.or(self.mode.map(Difficulty::speed)).or(profile)
+    }
+
+    /// The food mix from `--food`, classic by default.
+    pub fn food_mix(&self) -> FoodMix {
+        self.food.unwrap_or_default()
+    }
+
+    /// Settings for a game that can't ask anything, like a tournament's: the
+    /// options given, with the medium board, solid walls and seed 0 otherwise.
+    pub fn unattended_settings(&self) -> Settings {
+        let (width, height) = match &self.level {
+            Some(level) => (level.width, level.height),
+            None => self.board_size().unwrap_or((40, 20)),
+        };
+        let mut settings = Settings {
+            width,
+            height,
+            speed: self.initial_speed().unwrap_or(Difficulty::Normal.speed()),
+            seed: self.seed.unwrap_or(0),
+            walls: self.walls.unwrap_or(WallMode::Solid),
+            level: self.level.clone(),
+            players: 1,
+            profile: self.profile.clone(),
+            food_mix: self.food_mix(),
+        };
+        settings.speed = settings.clamped_speed();
+        settings
+    }
+}
+
+fn parse_format(s: &str) -> Result<Format, String> {
+    s.parse()
+}
+
+fn parse_difficulty(s: &str) -> Result<Difficulty, String> {
+    s.parse()
+}
+
+fn parse_food_mix(s: &str) -> Result<FoodMix, String> {
+    s.parse()
+}
+
+fn parse_profile(s: &str) -> Result<Profile, String> {
+    Profile::find(s)
+}
+
+fn parse_level(s: &str) -> Result<Level, String> {
+    Level::find(s).map_err(|err| err.to_string())
+}
+
+fn parse_wall_mode(s: &str) -> Result<WallMode, String> {
+    s.parse()
+}
@@ -0,0 +1,201 @@
+//! Steering snakes from outside the game loop.
+//!
+//! Every snake is driven by a [`SnakeController`]. Once per tick, just before
+//! the game steps, the controller is shown a read-only [`View`] of the board
+//! and picks the snake's direction. The keyboard, the autopilot and a player
+//! joined over the network are all controllers, and bots can be written the
+//! same way:
+//!
+//! ```
+//! use rusty_snake::controller::{SnakeController, View};
+//! use rusty_snake::game::Direction;
+//!
+//! /// Runs in circles.
+//! struct Dizzy;
+//!
+//! impl SnakeController for Dizzy {
+//!     fn next_direction(&mut self, view: &View) -> Direction {
+//!         match view.snake().direction {
+//!             Direction::Up => Direction::Right,
+//!             Direction::Right => Direction::Down,
+//!             Direction::Down => Direction::Left,
+//!             Direction::Left => Direction::Up,
+//!         }
+//!     }
+//! }
+//! ```
+
+use std::collections::{HashSet, VecDeque};
+
+use crate::autopilot;
+use crate::game::{Direction, FoodKind, GameEvent, GameState, Point, Snake, WallMode};
+
+/// Chooses a snake's direction each tick.
+pub trait SnakeController {
+    /// The direction to move in on the coming step. Reversing onto the
+    /// snake's own neck is ignored, as with the keyboard.
+    fn next_direction(&mut self, view: &View) -> Direction;
+
+    /// Whether the controller has given up, like a bot that crashed. Its
+    /// snake then forfeits the game; see [`step`].
+    fn has_forfeited(&self) -> bool {
+        false
+    }
+}
+
+/// What a controller can see: the whole board, from one player's side.
+#[derive(Debug, Clone, Copy)]
+pub struct View<'a> {
+    game: &'a GameState,
+    player: usize,
+}
+
+impl<'a> View<'a> {
+    pub fn new(game: &'a GameState, player: usize) -> Self {
+        View { game, player }
+    }
+
+    /// The player being controlled (0-based).
+    pub fn player(&self) -> usize {
+        self.player
+    }
+
+    /// The controlled snake.
+    pub fn snake(&self) -> &'a Snake {
+        &self.game.snakes[self.player]
+    }
+
+    /// Every snake, the controlled one included.
+    pub fn snakes(&self) -> &'a [Snake] {
+        &self.game.snakes
+    }
+
+    pub fn food(&self) -> Point {
+        self.game.food
+    }
+
+    pub fn food_kind(&self) -> FoodKind {
+        self.game.food_kind
+    }
+
+    /// Board width, walls included.
+    pub fn width(&self) -> i32 {
+        self.game.width
+    }
+
+    /// Board height, walls included.
+    pub fn height(&self) -> i32 {
+        self.game.height
+    }
+
+    pub fn walls(&self) -> WallMode {
+        self.game.walls
+    }
+
+    /// Wall cells inside the border.
+    pub fn obstacles(&self) -> &'a HashSet<Point> {
+        &self.game.obstacles
+    }
+
+    /// Number of steps taken so far.
+    pub fn tick(&self) -> u64 {
+        self.game.tick
+    }
+
+    /// The cell one move away; see [`GameState::next_cell`].
+    pub fn next_cell(&self, from: Point, direction: Direction) -> Option<Point> {
+        self.game.next_cell(from, direction)
+    }
+
+    /// The full game state, for anything the accessors above don't cover.
+    pub fn game(&self) -> &'a GameState {
+        self.game
+    }
+}
+
+/// How many presses [`KeyboardController`] holds for the coming ticks.
+pub const MAX_QUEUED_TURNS: usize = 3;
+
+/// Steers with the directions pressed, one turn per tick.
+///
+/// Presses wait in a short queue, so Up then Left pressed quickly while
+/// moving Right turns Up on one tick and Left on the next, rather than losing
+/// the Up. Each turn is checked against the direction the snake is moving in
+/// when it is applied: one that would reverse the snake, or that it is
+/// already taking, is dropped, so pressing Up then Down while moving Left
+/// goes Up and never back into the snake's own neck.
+#[derive(Debug, Clone, Default)]
+pub struct KeyboardController {
+    presses: VecDeque<Direction>,
+}
+
+impl KeyboardController {
+    pub fn new() -> Self {
+        KeyboardController::default()
+    }
+
+    /// Queues a turn. Repeating the last press, or pressing with the queue
+    /// full, does nothing.
+    pub fn press(&mut self, direction: Direction) {
+        if self.presses.len() < MAX_QUEUED_TURNS && self.presses.back() != Some(&direction) {
+            self.presses.push_back(direction);
+        }
+    }
+
+    /// Forgets presses not yet applied.
+    pub fn clear(&mut self) {
+        self.presses.clear();
+    }
+}
+
+impl SnakeController for KeyboardController {
+    fn next_direction(&mut self, view: &View) -> Direction {
+        let snake = view.snake();
+        while let Some(pressed) = self.presses.pop_front() {
+            if pressed != snake.direction && pressed != snake.direction.opposite() {
+                return pressed;
+            }
+        }
+        snake.next_direction
+    }
+}
+
+/// Steers with the built-in AI; see [`autopilot`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AutopilotController;
+
+impl SnakeController for AutopilotController {
+    fn next_direction(&mut self, view: &View) -> Direction {
+        autopilot::choose_direction(view.game(), view.player()).unwrap_or(view.snake().direction)
+    }
+}
+
+/// Asks each controller for its snake's direction, ready to pass to
+/// [`GameState::step`]. Dead snakes are skipped.
+pub fn collect_inputs(game: &GameState, controllers: &mut [&mut dyn SnakeController]) -> Vec<Option<Direction>> {
+    controllers
+        .iter_mut()
+        .enumerate()
+        .map(|(player, controller)| {
+            let alive = game.snakes.get(player).is_some_and(Snake::is_alive);
+            alive.then(|| controller.next_direction(&View::new(game, player)))
+        })
+        .collect()
+}
+
+/// Steps `game` with the controllers' moves, one controller per snake. If a
+/// controller has given up, its snake forfeits instead and the game ends.
+pub fn step(game: &mut GameState, controllers: &mut [&mut dyn SnakeController]) -> Vec<GameEvent> {
+    let inputs = collect_inputs(game, controllers);
+    let forfeits: Vec<usize> = controllers
+        .iter()
+        .enumerate()
+        .filter(|(_, controller)| controller.has_forfeited())
+        .map(|(player, _)| player)
+        .collect();
+    if forfeits.is_empty() {
+        game.step(&inputs)
+    } else {
+        game.forfeit(&forfeits)
+    }
+}
@@ -0,0 +1,346 @@
+//! A reinforcement-learning environment in the style of OpenAI Gym.
+//!
+//! [`SnakeEnv::reset`] starts a single-player game from a seed and returns the
+//! first [`Observation`]; [`SnakeEnv::step`] makes one move and returns the
+//! next observation, the reward, whether the episode is done, and an [`Info`]
+//! with the details. Nothing here touches the terminal or the clock, so an
+//! episode runs as fast as the game rules can be stepped.
+//!
+//! ```
+//! use rusty_snake::env::{Action, EnvConfig, SnakeEnv};
+//!
+//! let mut env = SnakeEnv::new(EnvConfig::default()).unwrap();
+//! let observation = env.reset(42);
+//! assert_eq!(observation.shape, [4, 10, 20]);
+//! loop {
+//!     // A real agent would pick an action from the observation
+//!     let (_observation, reward, done, info) = env.step(Action::Straight);
+//!     if done {
+//!         assert!(reward < 0.0, "ran into the wall after {} steps", info.tick);
+//!         break;
+//!     }
+//! }
+//! ```
+
+use crate::game::{
+    check_board_size, DeathCause, Difficulty, Direction, FoodMix, GameEvent, GameState, Point, Settings, WallMode,
+};
+use crate::level::Level;
+
+/// How the board is turned into numbers for an agent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+    /// The whole board as a `[4, height, width]` tensor of channels, walls
+    /// included:
+    ///
+    /// 0. the snake's head (1.0)
+    /// 1. its body, from near 0.0 at the tail up to 1.0 at the head
+    /// 2. the food (1.0)
+    /// 3. walls and obstacles (1.0); the border only counts in solid mode
+    #[default]
+    Grid,
+    /// Eleven 0/1 features, relative to where the snake is heading:
+    ///
+    /// - 0..3: danger straight ahead, to the left, to the right
+    /// - 3..7: food ahead, behind, to the left, to the right
+    /// - 7..11: the heading, one-hot in [`Direction::ALL`] order
+    ///
+    /// The food's position ignores wrap-around.
+    Features,
+}
+
+/// The reward for each thing that can happen on a step. They add up, so a
+/// step that eats earns `food + step`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rewards {
+    pub food: f32,
+    pub death: f32,
+    /// Earned on every step, usually a small penalty to discourage circling.
+    pub step: f32,
+}
+
+impl Default for Rewards {
+    fn default() -> Self {
+        Rewards {
+            food: 1.0,
+            death: -1.0,
+            step: 0.0,
+        }
+    }
+}
+
+/// What the environment plays on and how it reports back.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvConfig {
+    /// Board width, walls included.
+    pub width: i32,
+    /// Board height, walls included.
+    pub height: i32,
+    pub walls: WallMode,
+    /// Obstacle map to play on; its size replaces `width` and `height`.
+    pub level: Option<Level>,
+    pub encoding: Encoding,
+    pub rewards: Rewards,
+    /// Ends an episode that runs this long; see [`Info::truncated`].
+    pub max_steps: Option<u64>,
+}
+
+impl Default for EnvConfig {
+    fn default() -> Self {
+        EnvConfig {
+            width: 20,
+            height: 10,
+            walls: WallMode::Solid,
+            level: None,
+            encoding: Encoding::Grid,
+            rewards: Rewards::default(),
+            max_steps: Some(10_000),
+        }
+    }
+}
+
+/// A move, either relative to the snake's heading or as a compass direction.
+///
+/// A direction that would reverse the snake is ignored, as with the keyboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Straight,
+    TurnLeft,
+    TurnRight,
+    Go(Direction),
+}
+
+impl Action {
+    /// The relative actions, for agents with a three-way output.
+    pub const RELATIVE: [Action; 3] = [Action::Straight, Action::TurnLeft, Action::TurnRight];
+
+    /// The direction this action turns a snake heading `heading` to.
+    pub fn direction(self, heading: Direction) -> Direction {
+        match self {
+            Action::Straight => heading,
+            Action::TurnLeft => rotate(heading, true),
+            Action::TurnRight => rotate(heading, false),
+            Action::Go(direction) => direction,
+        }
+    }
+}
+
+impl From<Direction> for Action {
+    fn from(direction: Direction) -> Self {
+        Action::Go(direction)
+    }
+}
+
+/// A quarter turn, to the left or right.
+fn rotate(direction: Direction, left: bool) -> Direction {
+    match (direction, left) {
+        (Direction::Up, true) | (Direction::Down, false) => Direction::Left,
+        (Direction::Up, false) | (Direction::Down, true) => Direction::Right,
+        (Direction::Left, true) | (Direction::Right, false) => Direction::Down,
+        (Direction::Left, false) | (Direction::Right, true) => Direction::Up,
+    }
+}
+
+/// An encoded board: a flat row-major tensor and its shape.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Observation {
+    pub shape: Vec<usize>,
+    pub data: Vec<f32>,
+}
+
+/// Details about a step, beyond the reward.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Info {
+    pub score: i32,
+    /// The snake's length.
+    pub length: usize,
+    /// Steps taken this episode.
+    pub tick: u64,
+    /// Whether food was eaten on this step.
+    pub ate: bool,
+    /// Why the snake died, if it has.
+    pub death: Option<DeathCause>,
+    /// Whether the episode was cut off by `max_steps` rather than ended by the game.
+    pub truncated: bool,
+    /// Whether the snake won by filling the board, leaving no room for more food.
+    pub filled_board: bool,
+}
+
+/// A single-player game wrapped for training agents.
+#[derive(Debug, Clone)]
+pub struct SnakeEnv {
+    config: EnvConfig,
+    game: GameState,
+    done: bool,
+}
+
+impl SnakeEnv {
+    /// Creates the environment, ready to play with seed 0. The board must be
+    /// a size the game allows; see [`check_board_size`].
+    pub fn new(config: EnvConfig) -> Result<Self, String> {
+        match &config.level {
+            Some(level) => check_board_size(level.width, level.height)?,
+            None => check_board_size(config.width, config.height)?,
+        }
+        let game = GameState::new(&settings(&config, 0));
+        Ok(SnakeEnv {
+            config,
+            game,
+            done: false,
+        })
+    }
+
+    pub fn config(&self) -> &EnvConfig {
+        &self.config
+    }
+
+    /// The game being played, for rendering or custom encodings.
+    pub fn game(&self) -> &GameState {
+        &self.game
+    }
+
+    /// The shape of every observation this environment returns.
+    pub fn observation_shape(&self) -> Vec<usize> {
+        match self.config.encoding {
+            Encoding::Grid => vec![4, self.game.height as usize, self.game.width as usize],
+            Encoding::Features => vec![11],
+        }
+    }
+
+    /// Starts a new episode. The same seed and the same actions always play
+    /// out the same way.
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        self.game = GameState::new(&settings(&self.config, seed));
+        self.done = false;
+        self.observe()
+    }
+
+    /// Makes one move. Once an episode is done, further steps change nothing
+    /// and earn no reward until [`SnakeEnv::reset`].
+    pub fn step(&mut self, action: Action) -> (Observation, f32, bool, Info) {
+        if self.done {
+            return (self.observe(), 0.0, true, self.info(false));
+        }
+
+        let direction = action.direction(self.game.snakes[0].direction);
+        let events = self.game.step(&[Some(direction)]);
+        let ate = events.iter().any(|event| matches!(event, GameEvent::AteFood { .. }));
+
+        let rewards = self.config.rewards;
+        let mut reward = rewards.step;
+        if ate {
+            reward += rewards.food;
+        }
+        if !self.game.snakes[0].is_alive() {
+            reward += rewards.death;
+        }
+
+        let info = self.info(ate);
+        self.done = self.game.is_over() || info.truncated;
+        (self.observe(), reward, self.done, info)
+    }
+
+    /// Encodes the current board.
+    pub fn observe(&self) -> Observation {
+        let data = match self.config.encoding {
+            Encoding::Grid => self.grid(),
+            Encoding::Features => self.features(),
+        };
+        Observation {
+            shape: self.observation_shape(),
+            data,
+        }
+    }
+
+    fn info(&self, ate: bool) -> Info {
+        let snake = &self.game.snakes[0];
+        Info {
+            score: snake.score,
+            length: snake.body.len(),
+            tick: self.game.tick,
+            ate,
+            death: snake.death,
+            truncated: !self.game.is_over() && self.config.max_steps.is_some_and(|max| self.game.tick >= max),
+            filled_board: self.game.is_won(),
+        }
+    }
+
+    fn grid(&self) -> Vec<f32> {
+        let game = &self.game;
+        let (width, height) = (game.width as usize, game.height as usize);
+        let plane = width * height;
+        let index = |channel: usize, p: Point| channel * plane + p.y as usize * width + p.x as usize;
+        let mut data = vec![0.0; 4 * plane];
+
+        let snake = &game.snakes[0];
+        let length = snake.body.len() as f32;
+        for (i, &cell) in snake.body.iter().enumerate() {
+            data[index(1, cell)] = (i + 1) as f32 / length;
+        }
+        data[index(0, snake.head())] = 1.0;
+        if !game.is_won() {
+            data[index(2, game.food)] = 1.0;
+        }
+        for &cell in &game.obstacles {
+            data[index(3, cell)] = 1.0;
+        }
+        if game.walls == WallMode::Solid {
+            for x in 0..game.width {
+                data[index(3, Point { x, y: 0 })] = 1.0;
+                data[index(3, Point { x, y: game.height - 1 })] = 1.0;
+            }
+            for y in 0..game.height {
+                data[index(3, Point { x: 0, y })] = 1.0;
+                data[index(3, Point { x: game.width - 1, y })] = 1.0;
+            }
+        }
+        data
+    }
+
+    fn features(&self) -> Vec<f32> {
+        let game = &self.game;
+        let snake = &game.snakes[0];
+        let (head, heading) = (snake.head(), snake.direction);
+
+        // The game checks collisions before the tail moves, so the tail is as
+        // deadly as the rest of the body
+        let danger = |direction: Direction| match game.next_cell(head, direction) {
+            None => true,
+            Some(cell) => game.obstacles.contains(&cell) || game.snake_positions.contains_key(&cell),
+        };
+        let (fx, fy) = heading.vector();
+        let (lx, ly) = rotate(heading, true).vector();
+        let (dx, dy) = (game.food.x - head.x, game.food.y - head.y);
+        let ahead = dx * fx + dy * fy;
+        let left = dx * lx + dy * ly;
+
+        let flags = [
+            danger(heading),
+            danger(rotate(heading, true)),
+            danger(rotate(heading, false)),
+            ahead > 0,
+            ahead < 0,
+            left > 0,
+            left < 0,
+        ];
+        flags
+            .into_iter()
+            .chain(Direction::ALL.map(|direction| direction == heading))
+            .map(|flag| if flag { 1.0 } else { 0.0 })
+            .collect()
+    }
+}
+
+fn settings(config: &EnvConfig, seed: u64) -> Settings {
+    Settings {
+        width: config.width,
+        height: config.height,
+        speed: Difficulty::Normal.speed(),
+        seed,
+        walls: config.walls,
+        level: config.level.clone(),
+        players: 1,
+        profile: None,
+        food_mix: FoodMix::CLASSIC,
+    }
+}
@@ -0,0 +1,886 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::str::FromStr;
+
+use crate::level::Level;
+use crate::profile::Profile;
+
+/// Fastest allowed tick, in milliseconds.
+pub const MIN_SPEED: u64 = 50;
+/// Slowest allowed tick, in milliseconds.
+pub const MAX_SPEED: u64 = 500;
+/// How much `+`/`-` change the tick, in milliseconds.
+pub const SPEED_STEP: u64 = 50;
+/// Smallest board width or height, walls included.
+pub const MIN_BOARD_SIZE: i32 = 8;
+/// Largest board width or height, walls included.
+pub const MAX_BOARD_SIZE: i32 = 500;
+
+/// The shortest time, in milliseconds, the snake may take to cross the
+/// playfield's shorter side at full speed.
+const MIN_CROSSING: u64 = 500;
+
+/// The fastest and slowest ticks allowed on a `width` x `height` board.
+///
+/// Small boards don't go all the way down to `MIN_SPEED`, so there is still
+/// time to react before running into the far wall.
+pub fn speed_limits(width: i32, height: i32) -> (u64, u64) {
+    let short_side = (width.min(height) - 2).max(1) as u64;
+    let fastest = (MIN_CROSSING / short_side).div_ceil(SPEED_STEP) * SPEED_STEP;
+    (fastest.clamp(MIN_SPEED, MAX_SPEED), MAX_SPEED)
+}
+
+/// Checks that a `width` x `height` board is one that can be played on.
+pub fn check_board_size(width: i32, height: i32) -> Result<(), String> {
+    let range = MIN_BOARD_SIZE..=MAX_BOARD_SIZE;
+    if range.contains(&width) && range.contains(&height) {
+        Ok(())
+    } else {
+        Err(format!(
+            "a {}x{} board is not allowed (width and height must be {} to {})",
+            width, height, MIN_BOARD_SIZE, MAX_BOARD_SIZE
+        ))
+    }
+}
+
+/// The speed presets offered in the settings menu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    /// Milliseconds per tick for this preset.
+    pub fn speed(self) -> u64 {
+        match self {
+            Difficulty::Easy => 300,
+            Difficulty::Normal => 200,
+            Difficulty::Hard => 100,
+        }
+    }
+
+    /// The preset with exactly this speed, if any.
+    pub fn from_speed(speed: u64) -> Option<Difficulty> {
+        [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard]
+            .into_iter()
+            .find(|difficulty| difficulty.speed() == speed)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+        }
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "easy" => Ok(Difficulty::Easy),
+            "normal" => Ok(Difficulty::Normal),
+            "hard" => Ok(Difficulty::Hard),
+            _ => Err(format!("unknown difficulty `{}` (expected easy, normal or hard)", s)),
+        }
+    }
+}
+
+/// What happens when the snake reaches the edge of the board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WallMode {
+    /// Hitting the wall ends the game.
+    #[default]
+    Solid,
+    /// The snake leaves one edge and comes back in on the opposite one.
+    Wrap,
+}
+
+impl WallMode {
+    pub fn name(self) -> &'static str {
+        match self {
+            WallMode::Solid => "Solid",
+            WallMode::Wrap => "Wrap",
+        }
+    }
+}
+
+impl FromStr for WallMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "solid" => Ok(WallMode::Solid),
+            "wrap" => Ok(WallMode::Wrap),
+            _ => Err(format!("unknown wall mode `{}` (expected solid or wrap)", s)),
+        }
+    }
+}
+
+/// The kinds of food, each with its own effect when eaten.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FoodKind {
+    /// One point and one segment.
+    Regular,
+    /// Three points, if eaten before it disappears.
+    Bonus,
+    /// One point and three segments.
+    Golden,
+    /// Costs a point and two segments; it disappears if left alone.
+    Poison,
+}
+
+impl FoodKind {
+    pub const ALL: [FoodKind; 4] = [FoodKind::Regular, FoodKind::Bonus, FoodKind::Golden, FoodKind::Poison];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            FoodKind::Regular => "Regular",
+            FoodKind::Bonus => "Bonus",
+            FoodKind::Golden => "Golden",
+            FoodKind::Poison => "Poison",
+        }
+    }
+
+    /// Points for eating it; the score never drops below 0.
+    pub fn points(self) -> i32 {
+        match self {
+            FoodKind::Regular | FoodKind::Golden => 1,
+            FoodKind::Bonus => 3,
+            FoodKind::Poison => -1,
+        }
+    }
+
+    /// Segments the snake gains by eating it, or loses if negative: first from
+    /// any growth still to come, then from the body. The head always stays.
+    pub fn growth(self) -> i32 {
+        match self {
+            FoodKind::Regular | FoodKind::Bonus => 1,
+            FoodKind::Golden => 3,
+            FoodKind::Poison => -2,
+        }
+    }
+
+    /// Ticks it stays on the board before moving elsewhere, if it doesn't stay
+    /// until eaten.
+    pub fn lifetime(self) -> Option<u64> {
+        match self {
+            FoodKind::Regular | FoodKind::Golden => None,
+            FoodKind::Bonus => Some(40),
+            FoodKind::Poison => Some(60),
+        }
+    }
+}
+
+/// How often each kind of food spawns, as relative weights.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FoodMix {
+    pub regular: u32,
+    pub bonus: u32,
+    pub golden: u32,
+    pub poison: u32,
+}
+
+impl FoodMix {
+    /// Regular food only.
+    pub const CLASSIC: FoodMix = FoodMix {
+        regular: 1,
+        bonus: 0,
+        golden: 0,
+        poison: 0,
+    };
+
+    /// Mostly regular food, with the occasional bonus, golden or poison.
+    pub const MIXED: FoodMix = FoodMix {
+        regular: 12,
+        bonus: 3,
+        golden: 2,
+        poison: 3,
+    };
+
+    pub fn weight(self, kind: FoodKind) -> u32 {
+        match kind {
+            FoodKind::Regular => self.regular,
+            FoodKind::Bonus => self.bonus,
+            FoodKind::Golden => self.golden,
+            FoodKind::Poison => self.poison,
+        }
+    }
+
+    /// Picks a kind at random, each as likely as its weight allows.
+    pub fn choose(self, rng: &mut impl Rng) -> FoodKind {
+        let total: u32 = FoodKind::ALL.iter().map(|&kind| self.weight(kind)).sum();
+        let mut roll = rng.gen_range(0..total.max(1));
+        for kind in FoodKind::ALL {
+            if roll < self.weight(kind) {
+                return kind;
+            }
+            roll -= self.weight(kind);
+        }
+        FoodKind::Regular
+    }
+
+    /// Whether only regular food ever spawns.
+    pub fn is_classic(self) -> bool {
+        FoodKind::ALL.iter().all(|&kind| kind == FoodKind::Regular || self.weight(kind) == 0)
+    }
+}
+
+impl Default for FoodMix {
+    fn default() -> Self {
+        FoodMix::CLASSIC
+    }
+}
+
+impl FromStr for FoodMix {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "classic" => Ok(FoodMix::CLASSIC),
+            "mixed" => Ok(FoodMix::MIXED),
+            _ => Err(format!("unknown food mix `{}` (expected classic or mixed)", s)),
+        }
+    }
+}
+
+/// The settings a game is started with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Settings {
+    /// Board width, walls included.
+    pub width: i32,
+    /// Board height, walls included.
+    pub height: i32,
+    /// Starting milliseconds per tick.
+    pub speed: u64,
+    pub seed: u64,
+    pub walls: WallMode,
+    /// Obstacle map to play on; its size replaces `width` and `height`.
+    pub level: Option<Level>,
+    /// Number of snakes on the board, 1 or 2.
+    pub players: usize,
+    /// Speeds the game up as the score grows; `speed` is its starting tick.
+    pub profile: Option<Profile>,
+    /// Which kinds of food spawn, and how often.
+    pub food_mix: FoodMix,
+}
+
+impl Settings {
+    /// The size of the board played on, walls included: the level's, if any.
+    pub fn board_size(&self) -> (i32, i32) {
+        match &self.level {
+            Some(level) => (level.width, level.height),
+            None => (self.width, self.height),
+        }
+    }
+
+    /// The fastest and slowest ticks allowed on this board.
+    pub fn speed_limits(&self) -> (u64, u64) {
+        let (width, height) = self.board_size();
+        speed_limits(width, height)
+    }
+
+    /// The starting speed, brought within this board's limits.
+    pub fn clamped_speed(&self) -> u64 {
+        let (fastest, slowest) = self.speed_limits();
+        self.speed.clamp(fastest, slowest)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// Serialized in lowercase, e.g. `"up"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+
+    /// Returns `(dx, dy)` for each direction.
+    pub fn vector(self) -> (i32, i32) {
+        match self {
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+        }
+    }
+
+    /// Returns the direction pointing the other way.
+    pub fn opposite(self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+        }
+    }
+}
+
+/// Why a game ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeathCause {
+    Wall,
+    SelfCollision,
+    /// Ran into another snake's body.
+    Rival,
+    /// Met another snake's head; both die.
+    HeadOn,
+    /// Gave up, e.g. a bot that stopped answering.
+    Forfeit,
+}
+
+impl DeathCause {
+    /// The message shown to the player when a single-player game ends.
+    pub fn message(self) -> &'static str {
+        match self {
+            DeathCause::Wall => "Game Over! You hit the wall!",
+            DeathCause::SelfCollision => "Game Over! You hit yourself!",
+            DeathCause::Rival => "Game Over! You hit the other snake!",
+            DeathCause::HeadOn => "Game Over! You crashed head-on!",
+            DeathCause::Forfeit => "Game Over! You forfeited!",
+        }
+    }
+
+    /// What happened, for messages about a named player, e.g. "Player 2 hit the wall".
+    pub fn description(self) -> &'static str {
+        match self {
+            DeathCause::Wall => "hit the wall",
+            DeathCause::SelfCollision => "ran into itself",
+            DeathCause::Rival => "ran into the other snake",
+            DeathCause::HeadOn => "crashed head-on",
+            DeathCause::Forfeit => "forfeited",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameStatus {
+    Running,
+    Paused,
+    /// At least one snake died; see each snake's `death`.
+    Over,
+    /// The snakes filled the board, leaving nowhere to put the food.
+    Won,
+}
+
+/// Something that happened during a single `step`, for the front end to react to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameEvent {
+    /// A snake's head moved to `head`; `tail` is the cell its tail left, if
+    /// any. Poison can take more cells than that.
+    Moved {
+        player: usize,
+        head: Point,
+        tail: Option<Point>,
+    },
+    /// A snake ate the food at this point.
+    AteFood { player: usize, at: Point, kind: FoodKind },
+    /// New food was placed at this point, after the last was eaten or ran out of time.
+    FoodSpawned(Point),
+    /// A snake died, which ends the game.
+    Died { player: usize, cause: DeathCause },
+    /// No free cell was left for new food, which ends the game as a win.
+    BoardFilled,
+}
+
+/// One player's snake.
+#[derive(Debug, Clone)]
+pub struct Snake {
+    /// The last element is the head.
+    pub body: VecDeque<Point>,
+    pub direction: Direction,
+    pub next_direction: Direction,
+    pub score: i32,
+    /// Segments still to add, one per move, after eating golden food.
+    pub growing: u32,
+    /// Why the snake died, once it has.
+    pub death: Option<DeathCause>,
+}
+
+impl Snake {
+    fn new(start: Point, direction: Direction) -> Self {
+        let mut body = VecDeque::new();
+        body.push_back(start);
+        Snake {
+            body,
+            direction,
+            next_direction: direction,
+            score: 0,
+            growing: 0,
+            death: None,
+        }
+    }
+
+    pub fn head(&self) -> Point {
+        *self.body.back().unwrap()
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.death.is_none()
+    }
+}
+
+/// The complete state of one game, independent of any terminal I/O.
+#[derive(Debug, Clone)]
+pub struct GameState {
+    pub width: i32,
+    pub height: i32,
+    /// One snake per player; player 1 is index 0.
+    pub snakes: Vec<Snake>,
+    /// Which snake occupies each cell, for quick collision checks.
+    pub snake_positions: HashMap<Point, usize>,
+    /// Wall cells inside the border, from the level.
+    pub obstacles: HashSet<Point>,
+    pub food: Point,
+    pub food_kind: FoodKind,
+    /// The tick on which the food moves elsewhere, if it doesn't wait to be eaten.
+    pub food_expires: Option<u64>,
+    pub food_mix: FoodMix,
+    pub status: GameStatus,
+    /// Milliseconds per tick.
+    pub speed: u64,
+    /// Number of ticks the snakes have moved.
+    pub tick: u64,
+    /// The seed `rng` was created from, so a run can be replayed.
+    pub seed: u64,
+    pub walls: WallMode,
+    pub profile: Option<Profile>,
+    /// The best score reached so far. Poison can lower a score, but not this,
+    /// so the profile's level and speed never go back.
+    pub peak_score: i32,
+    rng: ChaCha8Rng,
+}
+
+impl GameState {
+    /// Starts a new game with the given settings.
+    ///
+    /// All randomness comes from the seed, so two games with the same settings
+    /// and the same inputs play out identically.
+    pub fn new(settings: &Settings) -> Self {
+        let (width, height, obstacles) = match &settings.level {
+            Some(level) => (level.width, level.height, level.obstacles.clone()),
+            None => (settings.width, settings.height, HashSet::new()),
+        };
+
+        let mut game = GameState {
+            width,
+            height,
+            snakes: Vec::new(),
+            snake_positions: HashMap::new(),
+            obstacles,
+            food: Point {
+                x: (width * 3 / 4).clamp(1, width - 2),
+                y: (height * 3 / 4).clamp(1, height - 2),
+            },
+            food_kind: FoodKind::Regular,
+            food_expires: None,
+            food_mix: settings.food_mix,
+            status: GameStatus::Running,
+            speed: settings.clamped_speed(),
+            tick: 0,
+            seed: settings.seed,
+            walls: settings.walls,
+            profile: settings.profile.clone(),
+            peak_score: 0,
+            rng: ChaCha8Rng::seed_from_u64(settings.seed),
+        };
+
+        for (start, direction) in game.starts(settings) {
+            game.snake_positions.insert(start, game.snakes.len());
+            game.snakes.push(Snake::new(start, direction));
+        }
+
+        // The first food goes ahead of and below a centered snake, unless that
+        // is inside a level's walls or on a snake
+        if game.obstacles.contains(&game.food) || game.snake_positions.contains_key(&game.food) {
+            if let Some(food) = game.random_free_point() {
+                game.food = food;
+            }
+        }
+        game
+    }
+
+    /// Where each player's snake starts, and which way it heads.
+    ///
+    /// A single snake starts at the level's `S`, or in the center moving Right.
+    /// With two players the first snake starts in the upper left instead, and
+    /// the second starts point-opposite it, heading the other way, so they
+    /// pass rather than meet head-on.
+    fn starts(&self, settings: &Settings) -> Vec<(Point, Direction)> {
+        let first = match &settings.level {
+            Some(level) => (level.start, level.direction),
+            None if settings.players > 1 => (
+                Point {
+                    x: self.width / 4,
+                    y: self.height / 3,
+                },
+                Direction::Right,
+            ),
+            // Start in the center, moving to the Right by default
+            None => (
+                Point {
+                    x: self.width / 2,
+                    y: self.height / 2,
+                },
+                Direction::Right,
+            ),
+        };
+        let mut starts = vec![first];
+
+        if settings.players > 1 {
+            let mirrored = Point {
+                x: self.width - 1 - first.0.x,
+                y: self.height - 1 - first.0.y,
+            };
+            starts.push((self.nearest_open_cell(mirrored, first.0), first.1.opposite()));
+        }
+        starts
+    }
+
+    /// The floor cell closest to `target` that is not `taken`.
+    fn nearest_open_cell(&self, target: Point, taken: Point) -> Point {
+        let is_open = |p: Point| {
+            p != taken && p.x >= 1 && p.y >= 1 && p.x < self.width - 1 && p.y < self.height - 1 && !self.obstacles.contains(&p)
+        };
+        (0..self.width.max(self.height))
+            .flat_map(|r| (-r..=r).flat_map(move |dx| (-r..=r).map(move |dy| (dx, dy))))
+            .map(|(dx, dy)| Point {
+                x: target.x + dx,
+                y: target.y + dy,
+            })
+            .find(|p| is_open(*p))
+            .unwrap_or(target)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.status == GameStatus::Paused
+    }
+
+    /// Whether the game has ended, by a death or by filling the board.
+    pub fn is_over(&self) -> bool {
+        matches!(self.status, GameStatus::Over | GameStatus::Won)
+    }
+
+    /// Whether the game ended with the board full.
+    pub fn is_won(&self) -> bool {
+        self.status == GameStatus::Won
+    }
+
+    /// In a multiplayer game that is over, the only snake still alive, or the
+    /// one with the higher score if they filled the board.
+    ///
+    /// `None` while the game runs, for a single player, or when it is a draw.
+    pub fn winner(&self) -> Option<usize> {
+        if !self.is_over() || self.snakes.len() < 2 {
+            return None;
+        }
+        let best = self.snakes.iter().map(|snake| snake.score).max();
+        let mut leaders = self.snakes.iter().enumerate().filter(|(_, snake)| match self.status {
+            GameStatus::Won => Some(snake.score) == best,
+            _ => snake.is_alive(),
+        });
+        match (leaders.next(), leaders.next()) {
+            (Some((player, _)), None) => Some(player),
+            _ => None,
+        }
+    }
+
+    /// Queues a turn for a player's next tick, ignoring reversals.
+    pub fn turn(&mut self, player: usize, direction: Direction) {
+        if let Some(snake) = self.snakes.get_mut(player) {
+            if direction != snake.direction.opposite() {
+                snake.next_direction = direction;
+            }
+        }
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.status = match self.status {
+            GameStatus::Running => GameStatus::Paused,
+            GameStatus::Paused => GameStatus::Running,
+            over => over,
+        };
+    }
+
+    /// Shortens the tick, down to the board's fastest (see [`speed_limits`]).
+    pub fn faster(&mut self) {
+        let (fastest, _) = speed_limits(self.width, self.height);
+        self.speed = self.speed.saturating_sub(SPEED_STEP).max(fastest).min(self.speed);
+    }
+
+    /// Lengthens the tick, up to the board's slowest.
+    pub fn slower(&mut self) {
+        let (_, slowest) = speed_limits(self.width, self.height);
+        self.speed = (self.speed + SPEED_STEP).min(slowest).max(self.speed);
+    }
+
+    /// Ends the game with `players` forfeiting, returning the events to report.
+    ///
+    /// Does nothing once the game is over.
+    pub fn forfeit(&mut self, players: &[usize]) -> Vec<GameEvent> {
+        let mut events = Vec::new();
+        if self.is_over() {
+            return events;
+        }
+        for &player in players {
+            if let Some(snake) = self.snakes.get_mut(player) {
+                let cause = DeathCause::Forfeit;
+                snake.death = Some(cause);
+                events.push(GameEvent::Died { player, cause });
+            }
+        }
+        if !events.is_empty() {
+            self.status = GameStatus::Over;
+        }
+        events
+    }
+
+    /// Advances the game by one tick. `inputs[i]` optionally turns player `i`
+    /// first; players past the end of `inputs` keep their queued direction.
+    ///
+    /// All snakes move at once. A snake dies if its head hits a wall, any
+    /// snake's body, or another head moving into the same cell (head-on, in
+    /// which case both die). Any death ends the game. So does eating when no
+    /// free cell is left for the next food, which wins it. What eating does
+    /// depends on the [`FoodKind`]; food that outstays its lifetime moves.
+    ///
+    /// Does nothing unless the game is running.
+    pub fn step(&mut self, inputs: &[Option<Direction>]) -> Vec<GameEvent> {
+        let mut events = Vec::new();
+        if self.status != GameStatus::Running {
+            return events;
+        }
+        for (player, input) in inputs.iter().enumerate() {
+            if let Some(direction) = *input {
+                self.turn(player, direction);
+            }
+        }
+        self.tick += 1;
+
+        // Calculate every living snake's new head position
+        let mut moves = Vec::new();
+        let mut deaths = Vec::new();
+        for player in 0..self.snakes.len() {
+            let snake = &mut self.snakes[player];
+            if !snake.is_alive() {
+                continue;
+            }
+            // Update direction from the queued next_direction
+            snake.direction = snake.next_direction;
+            let (head, direction) = (snake.head(), snake.direction);
+
+            // Check collisions: walls
+            match self.next_cell(head, direction) {
+                Some(new_head) => moves.push((player, new_head)),
+                None => deaths.push((player, DeathCause::Wall)),
+            }
+        }
+
+        for &(player, new_head) in &moves {
+            let head_on = moves.iter().any(|&(other, other_head)| {
+                other != player
+                    && (other_head == new_head
+                        // Two heads swapping cells also meet head-on
+                        || (other_head == self.snakes[player].head() && new_head == self.snakes[other].head()))
+            });
+            let cause = if self.obstacles.contains(&new_head) {
+                // Check collisions: obstacles
+                Some(DeathCause::Wall)
+            } else if head_on {
+                Some(DeathCause::HeadOn)
+            } else {
+                // Check collisions: self and other snakes
+                match self.snake_positions.get(&new_head) {
+                    Some(&owner) if owner == player => Some(DeathCause::SelfCollision),
+                    Some(_) => Some(DeathCause::Rival),
+                    None => None,
+                }
+            };
+            if let Some(cause) = cause {
+                deaths.push((player, cause));
+            }
+        }
+        deaths.sort_by_key(|&(player, _)| player);
+
+        if !deaths.is_empty() {
+            for (player, cause) in deaths {
+                self.snakes[player].death = Some(cause);
+                events.push(GameEvent::Died { player, cause });
+            }
+            self.status = GameStatus::Over;
+            return events;
+        }
+
+        // Update snakes
+        let before = self.peak_score;
+        let mut ate = false;
+        for (player, new_head) in moves {
+            let snake = &mut self.snakes[player];
+            snake.body.push_back(new_head);
+            self.snake_positions.insert(new_head, player);
+
+            let eaten = (new_head == self.food).then_some(self.food_kind);
+            let mut shrink = 0;
+            if let Some(kind) = eaten {
+                // Ate the food
+                snake.score = (snake.score + kind.points()).max(0);
+                snake.growing += kind.growth().max(1) as u32 - 1;
+                // Poison cancels growth still to come before taking segments
+                let cut = (-kind.growth()).max(0) as u32;
+                let cancelled = cut.min(snake.growing);
+                snake.growing -= cancelled;
+                shrink = (cut - cancelled) as usize;
+                ate = true;
+            }
+            // Growing keeps the tail where it is; otherwise pop it
+            let tail = if eaten.is_some_and(|kind| kind.growth() > 0) {
+                None
+            } else if snake.growing > 0 {
+                snake.growing -= 1;
+                None
+            } else {
+                let tail = snake.body.pop_front().unwrap();
+                self.snake_positions.remove(&tail);
+                Some(tail)
+            };
+            // Poison takes the rest from the body, but never the head
+            for _ in 0..shrink.min(snake.body.len() - 1) {
+                let tail = snake.body.pop_front().unwrap();
+                self.snake_positions.remove(&tail);
+            }
+            events.push(GameEvent::Moved {
+                player,
+                head: new_head,
+                tail,
+            });
+            if let Some(kind) = eaten {
+                events.push(GameEvent::AteFood { player, at: new_head, kind });
+            }
+        }
+        if ate {
+            self.peak_score = self.peak_score.max(self.best_score());
+            self.speed_up(before);
+            match self.spawn_food() {
+                Some(food) => events.push(GameEvent::FoodSpawned(food)),
+                None => {
+                    self.status = GameStatus::Won;
+                    events.push(GameEvent::BoardFilled);
+                }
+            }
+        } else if self.food_expires.is_some_and(|expires| self.tick >= expires) {
+            // Left too long: it moves, and may turn into another kind
+            if let Some(food) = self.spawn_food() {
+                events.push(GameEvent::FoodSpawned(food));
+            }
+        }
+
+        events
+    }
+
+    /// The highest score on the board.
+    pub fn best_score(&self) -> i32 {
+        self.snakes.iter().map(|snake| snake.score).max().unwrap_or(0)
+    }
+
+    /// The profile's level for the peak score, or `None` without a profile.
+    pub fn level(&self) -> Option<u32> {
+        Some(self.profile.as_ref()?.level(self.peak_score))
+    }
+
+    /// Moves the tick along the profile's curve after the peak score went up
+    /// from `before`. Leaves it alone while the curve stays put, so `+`/`-`
+    /// still work in between.
+    fn speed_up(&mut self, before: i32) {
+        let Some(profile) = &self.profile else {
+            return;
+        };
+        if self.peak_score <= before {
+            return;
+        }
+        let tick = profile.tick(self.peak_score);
+        if tick != profile.tick(before) {
+            let (fastest, slowest) = speed_limits(self.width, self.height);
+            self.speed = tick.clamp(fastest, slowest);
+        }
+    }
+
+    /// The cell one move from `from` in `direction`, wrapping around the edges
+    /// in wrap mode. `None` if the move runs into a solid border wall.
+    ///
+    /// Obstacles and snakes are not checked.
+    pub fn next_cell(&self, from: Point, direction: Direction) -> Option<Point> {
+        let (dx, dy) = direction.vector();
+        let next = Point {
+            x: from.x + dx,
+            y: from.y + dy,
+        };
+        if next.x < 1 || next.x >= self.width - 1 || next.y < 1 || next.y >= self.height - 1 {
+            match self.walls {
+                WallMode::Solid => None,
+                WallMode::Wrap => Some(self.wrap(next)),
+            }
+        } else {
+            Some(next)
+        }
+    }
+
+    /// Brings a point that left the playfield back in on the opposite edge.
+    fn wrap(&self, point: Point) -> Point {
+        let wrap_axis = |v: i32, size: i32| (v - 1).rem_euclid(size - 2) + 1;
+        Point {
+            x: wrap_axis(point.x, self.width),
+            y: wrap_axis(point.y, self.height),
+        }
+    }
+
+    /// Places the next food on a random free cell, choosing its kind by the
+    /// food mix. Returns where it went, or `None` if no cell is free.
+    fn spawn_food(&mut self) -> Option<Point> {
+        let food = self.random_free_point()?;
+        // Classic games draw nothing more, so they play out as they always have
+        let kind = if self.food_mix.is_classic() {
+            FoodKind::Regular
+        } else {
+            self.food_mix.choose(&mut self.rng)
+        };
+        self.food = food;
+        self.food_kind = kind;
+        self.food_expires = kind.lifetime().map(|lifetime| self.tick + lifetime);
+        Some(food)
+    }
+
+    /// Picks a random cell inside the walls that the snakes and obstacles do
+    /// not occupy, or `None` if every cell is taken.
+    ///
+    /// The cell is drawn from the free cells themselves, so this takes the same
+    /// time however full the board is.
+    fn random_free_point(&mut self) -> Option<Point> {
+        let free: Vec<Point> = (1..self.height - 1)
+            .flat_map(|y| (1..self.width - 1).map(move |x| Point { x, y }))
+            .filter(|point| !self.snake_positions.contains_key(point) && !self.obstacles.contains(point))
+            .collect();
+        if free.is_empty() {
+            return None;
+        }
+        Some(free[self.rng.gen_range(0..free.len())])
+    }
+}
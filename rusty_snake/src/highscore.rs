@@ -0,0 +1,120 @@
+//! Persistent high-score tables, one per board size and difficulty or profile.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::game::{Difficulty, Settings, WallMode};
+
+/// How many entries each table keeps.
+pub const TABLE_SIZE: usize = 10;
+
+/// Longest player name accepted.
+pub const MAX_NAME_LEN: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    pub name: String,
+    pub score: i32,
+    /// The seed of the game, so the run can be replayed.
+    pub seed: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HighScores {
+    /// Tables keyed by [`HighScores::key`], each sorted best first.
+    tables: BTreeMap<String, Vec<Entry>>,
+}
+
+impl HighScores {
+    /// The table name for a game's board and starting speed or profile, e.g.
+    /// `40x20 Normal`, `40x20 150ms Wrap`, `Pillars Hard` or `40x20 Arcade Mixed`.
+    pub fn key(settings: &Settings) -> String {
+        let board = match &settings.level {
+            Some(level) => level.name.clone(),
+            None => format!("{}x{}", settings.width, settings.height),
+        };
+        HighScores::key_for_board(&board, settings)
+    }
+
+    /// The table name for a campaign, e.g. `Campaign Normal`.
+    pub fn campaign_key(settings: &Settings) -> String {
+        HighScores::key_for_board("Campaign", settings)
+    }
+
+    fn key_for_board(board: &str, settings: &Settings) -> String {
+        let mut key = match (&settings.profile, Difficulty::from_speed(settings.speed)) {
+            (Some(profile), _) => format!("{} {}", board, profile),
+            (None, Some(difficulty)) => format!("{} {}", board, difficulty.name()),
+            (None, None) => format!("{} {}ms", board, settings.speed),
+        };
+        if settings.walls == WallMode::Wrap {
+            key.push_str(" Wrap");
+        }
+        if !settings.food_mix.is_classic() {
+            key.push_str(" Mixed");
+        }
+        key
+    }
+
+    /// The default location of the high-score file, under the user's data directory.
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("rusty_snake").join("highscores.json"))
+    }
+
+    /// Loads the tables from `path`.
+    ///
+    /// A missing file gives empty tables. A corrupt one is moved aside to
+    /// `<path>.bak`, so the next save does not destroy it, and also gives
+    /// empty tables.
+    pub fn load(path: &Path) -> HighScores {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(_) => return HighScores::default(),
+        };
+        match serde_json::from_str(&contents) {
+            Ok(scores) => scores,
+            Err(_) => {
+                let _ = fs::rename(path, path.with_extension("json.bak"));
+                HighScores::default()
+            }
+        }
+    }
+
+    /// Writes the tables to `path`, creating its directory if needed.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json)
+    }
+
+    /// The entries of one table, best first.
+    pub fn table(&self, key: &str) -> &[Entry] {
+        self.tables.get(key).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Whether `score` would make it onto the table.
+    pub fn qualifies(&self, key: &str, score: i32) -> bool {
+        let table = self.table(key);
+        score > 0 && (table.len() < TABLE_SIZE || table.iter().any(|entry| score > entry.score))
+    }
+
+    /// Adds an entry, keeping the table sorted and trimmed.
+    ///
+    /// Returns the entry's 0-based rank, or `None` if it did not make the table.
+    pub fn insert(&mut self, key: &str, entry: Entry) -> Option<usize> {
+        let table = self.tables.entry(key.to_string()).or_default();
+        // Ties go below existing entries, so the earlier score keeps its rank
+        let rank = table.iter().position(|e| entry.score > e.score).unwrap_or(table.len());
+        if rank >= TABLE_SIZE {
+            return None;
+        }
+        table.insert(rank, entry);
+        table.truncate(TABLE_SIZE);
+        Some(rank)
+    }
+}
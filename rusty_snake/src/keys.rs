@@ -0,0 +1,374 @@
+//! Which keys do what in a game.
+//!
+//! Three presets are built in: `arrows` (the default), `wasd` and `vim`. Any
+//! of their bindings can be changed in `keys.json` under the user's config
+//! directory:
+//!
+//! ```text
+//! {
+//!   "preset": "vim",
+//!   "bindings": {
+//!     "restart": ["r", "F5"],
+//!     "quit": ["q", "Esc"]
+//!   }
+//! }
+//! ```
+//!
+//! The keys given for an action replace the preset's keys for it. Ctrl+C
+//! always quits, whatever the bindings say.
+//!
+//! A key can't do two things, player 2's moves included.
+
+use crossterm::event::KeyCode;
+use crate::game::Direction;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Something a key can do in a game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    Pause,
+    Faster,
+    Slower,
+    Quit,
+    /// Start the game over with the same settings.
+    Restart,
+    /// Turn the autopilot on or off.
+    Autopilot,
+    /// Show the bindings.
+    Help,
+    /// Player 2's moves, in a two-player game on one keyboard.
+    Up2,
+    Down2,
+    Left2,
+    Right2,
+}
+
+impl Action {
+    pub const ALL: [Action; 15] = [
+        Action::Up,
+        Action::Down,
+        Action::Left,
+        Action::Right,
+        Action::Pause,
+        Action::Faster,
+        Action::Slower,
+        Action::Quit,
+        Action::Restart,
+        Action::Autopilot,
+        Action::Help,
+        Action::Up2,
+        Action::Down2,
+        Action::Left2,
+        Action::Right2,
+    ];
+
+    /// The name used in `keys.json`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Up => "up",
+            Action::Down => "down",
+            Action::Left => "left",
+            Action::Right => "right",
+            Action::Up2 => "p2_up",
+            Action::Down2 => "p2_down",
+            Action::Left2 => "p2_left",
+            Action::Right2 => "p2_right",
+            Action::Pause => "pause",
+            Action::Faster => "faster",
+            Action::Slower => "slower",
+            Action::Quit => "quit",
+            Action::Restart => "restart",
+            Action::Autopilot => "autopilot",
+            Action::Help => "help",
+        }
+    }
+
+    /// What the action does, for the in-game help.
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::Up => "Move up",
+            Action::Down => "Move down",
+            Action::Left => "Move left",
+            Action::Right => "Move right",
+            Action::Up2 => "Player 2 up",
+            Action::Down2 => "Player 2 down",
+            Action::Left2 => "Player 2 left",
+            Action::Right2 => "Player 2 right",
+            Action::Pause => "Pause/resume",
+            Action::Faster => "Speed up",
+            Action::Slower => "Slow down",
+            Action::Quit => "Quit",
+            Action::Restart => "Restart",
+            Action::Autopilot => "Autopilot on/off",
+            Action::Help => "Show this help",
+        }
+    }
+
+    /// The player (0-based) and direction, if this is a move.
+    pub fn turn(self) -> Option<(usize, Direction)> {
+        match self {
+            Action::Up => Some((0, Direction::Up)),
+            Action::Down => Some((0, Direction::Down)),
+            Action::Left => Some((0, Direction::Left)),
+            Action::Right => Some((0, Direction::Right)),
+            Action::Up2 => Some((1, Direction::Up)),
+            Action::Down2 => Some((1, Direction::Down)),
+            Action::Left2 => Some((1, Direction::Left)),
+            Action::Right2 => Some((1, Direction::Right)),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Action {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Action::ALL
+            .into_iter()
+            .find(|action| action.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("no action named `{}`", s))
+    }
+}
+
+/// Reads a key name: a single character, or one of `Up`, `Down`, `Left`,
+/// `Right`, `Space`, `Tab`, `Enter`, `Esc`, `Backspace` or `F1`-`F12`.
+pub fn parse_key(s: &str) -> Result<KeyCode, String> {
+    let mut chars = s.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Ok(KeyCode::Char(c.to_ascii_lowercase()));
+    }
+    let key = match s.to_ascii_lowercase().as_str() {
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "space" => KeyCode::Char(' '),
+        "tab" => KeyCode::Tab,
+        "enter" => KeyCode::Enter,
+        "esc" => KeyCode::Esc,
+        "backspace" => KeyCode::Backspace,
+        other => match other.strip_prefix('f').and_then(|n| n.parse().ok()) {
+            Some(n @ 1..=12) => KeyCode::F(n),
+            _ => return Err(format!("unknown key `{}`", s)),
+        },
+    };
+    Ok(key)
+}
+
+/// The name of a key, as [`parse_key`] reads it.
+pub fn key_name(key: KeyCode) -> String {
+    match key {
+        KeyCode::Char(' ') => "Space".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::Up => "Up".to_string(),
+        KeyCode::Down => "Down".to_string(),
+        KeyCode::Left => "Left".to_string(),
+        KeyCode::Right => "Right".to_string(),
+        KeyCode::Tab => "Tab".to_string(),
+        KeyCode::Enter => "Enter".to_string(),
+        KeyCode::Esc => "Esc".to_string(),
+        KeyCode::Backspace => "Backspace".to_string(),
+        KeyCode::F(n) => format!("F{}", n),
+        other => format!("{:?}", other),
+    }
+}
+
+#[derive(Debug)]
+pub enum KeysError {
+    Io(io::Error),
+    Parse(serde_json::Error),
+    UnknownPreset(String),
+    /// An action or key in the file could not be read.
+    Invalid(String),
+    /// The same key does two things.
+    Conflict { key: String, first: Action, second: Action },
+}
+
+impl fmt::Display for KeysError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeysError::Io(err) => write!(f, "{}", err),
+            KeysError::Parse(err) => write!(f, "{}", err),
+            KeysError::UnknownPreset(name) => {
+                write!(f, "no key preset named `{}` (try {})", name, KeyBindings::PRESETS.join(", "))
+            }
+            KeysError::Invalid(message) => write!(f, "{}", message),
+            KeysError::Conflict { key, first, second } => {
+                write!(f, "`{}` is bound to both {} and {}", key, first.name(), second.name())
+            }
+        }
+    }
+}
+
+impl std::error::Error for KeysError {}
+
+impl From<io::Error> for KeysError {
+    fn from(err: io::Error) -> Self {
+        KeysError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for KeysError {
+    fn from(err: serde_json::Error) -> Self {
+        KeysError::Parse(err)
+    }
+}
+
+/// The keys for each action.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyBindings {
+    /// One entry per action, in [`Action::ALL`] order.
+    keys: Vec<(Action, Vec<KeyCode>)>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings::preset("arrows").expect("the arrows preset is built in")
+    }
+}
+
+impl KeyBindings {
+    pub const PRESETS: [&'static str; 3] = ["arrows", "wasd", "vim"];
+
+    /// A built-in set of bindings.
+    ///
+    /// `arrows` steers player 1 with the arrow keys and player 2 with WASD;
+    /// `wasd` swaps them; `vim` steers player 1 with hjkl and player 2 with
+    /// WASD. The other actions are the same in all three.
+    pub fn preset(name: &str) -> Result<KeyBindings, KeysError> {
+        use KeyCode::{Char, Down, Left, Right, Up};
+        let arrows = [Up, Down, Left, Right];
+        let wasd = [Char('w'), Char('s'), Char('a'), Char('d')];
+        let vim = [Char('k'), Char('j'), Char('h'), Char('l')];
+        let (player1, player2) = match name.to_ascii_lowercase().as_str() {
+            "arrows" => (arrows, wasd),
+            "wasd" => (wasd, arrows),
+            "vim" => (vim, wasd),
+            _ => return Err(KeysError::UnknownPreset(name.to_string())),
+        };
+
+        let keys = Action::ALL
+            .into_iter()
+            .map(|action| {
+                let keys = match action {
+                    Action::Up => vec![player1[0]],
+                    Action::Down => vec![player1[1]],
+                    Action::Left => vec![player1[2]],
+                    Action::Right => vec![player1[3]],
+                    Action::Up2 => vec![player2[0]],
+                    Action::Down2 => vec![player2[1]],
+                    Action::Left2 => vec![player2[2]],
+                    Action::Right2 => vec![player2[3]],
+                    Action::Pause => vec![Char(' ')],
+                    Action::Faster => vec![Char('+'), Char('=')],
+                    Action::Slower => vec![Char('-')],
+                    Action::Quit => vec![Char('q')],
+                    Action::Restart => vec![Char('r')],
+                    Action::Autopilot => vec![KeyCode::Tab],
+                    Action::Help => vec![Char('?')],
+                };
+                (action, keys)
+            })
+            .collect();
+        Ok(KeyBindings { keys })
+    }
+
+    /// The default location of the key file, under the user's config directory.
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("rusty_snake").join("keys.json"))
+    }
+
+    /// Loads the bindings from the file at `path`, starting from `preset` if
+    /// given, otherwise the file's preset. A missing file gives the preset alone.
+    pub fn load(path: &Path, preset: Option<&str>) -> Result<KeyBindings, KeysError> {
+        let file: KeyFile = match fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents)?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => KeyFile::default(),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut bindings = KeyBindings::preset(preset.or(file.preset.as_deref()).unwrap_or("arrows"))?;
+        for (name, keys) in &file.bindings {
+            let action: Action = name.parse().map_err(KeysError::Invalid)?;
+            let keys = keys
+                .iter()
+                .map(|key| parse_key(key).map_err(|message| KeysError::Invalid(format!("{}: {}", name, message))))
+                .collect::<Result<Vec<_>, _>>()?;
+            bindings.set(action, keys);
+        }
+        bindings.check()?;
+        Ok(bindings)
+    }
+
+    /// The action bound to `key`, if any.
+    pub fn action(&self, key: KeyCode) -> Option<Action> {
+        let key = match key {
+            KeyCode::Char(c) => KeyCode::Char(c.to_ascii_lowercase()),
+            other => other,
+        };
+        self.keys
+            .iter()
+            .find(|(_, keys)| keys.contains(&key))
+            .map(|(action, _)| *action)
+    }
+
+    /// The keys bound to `action`.
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        self.keys
+            .iter()
+            .find(|(bound, _)| *bound == action)
+            .map_or(&[], |(_, keys)| keys.as_slice())
+    }
+
+    /// The keys bound to `action` as text, e.g. `+ or =`.
+    pub fn describe(&self, action: Action) -> String {
+        let names: Vec<String> = self.keys(action).iter().map(|&key| key_name(key)).collect();
+        if names.is_empty() {
+            "(unbound)".to_string()
+        } else {
+            names.join(" or ")
+        }
+    }
+
+    fn set(&mut self, action: Action, keys: Vec<KeyCode>) {
+        if let Some((_, bound)) = self.keys.iter_mut().find(|(bound, _)| *bound == action) {
+            *bound = keys;
+        }
+    }
+
+    /// Fails if a key is bound to more than one action.
+    fn check(&self) -> Result<(), KeysError> {
+        for (i, (first, keys)) in self.keys.iter().enumerate() {
+            for (second, others) in &self.keys[i + 1..] {
+                if let Some(&key) = keys.iter().find(|key| others.contains(key)) {
+                    return Err(KeysError::Conflict {
+                        key: key_name(key),
+                        first: *first,
+                        second: *second,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The contents of `keys.json`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct KeyFile {
+    preset: Option<String>,
+    /// Action names to key names.
+    bindings: BTreeMap<String, Vec<String>>,
+}
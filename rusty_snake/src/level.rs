@@ -0,0 +1,275 @@
+//! Obstacle levels loaded from plain-text maps.
+//!
+//! A level file starts with optional `key: value` metadata lines, followed by
+//! the map itself:
+//!
+//! ```text
+//! name: Pillars
+//! direction: right
+//! target: 10
+//!
+//! ##########
+//! #........#
+//! #.##..##.#
+//! #........#
+//! #...S....#
+//! #........#
+//! #.##..##.#
+//! ##########
+//! ```
+//!
+//! In the map `#` is a wall, `.` is floor and `S` is where the snake starts.
+//! The outer ring of the map is the board's border and must be all walls. The
+//! map must be a board size the game allows (see [`check_board_size`]), with
+//! at least one floor cell for the food. Nothing but blank lines may follow
+//! the map.
+//! `direction` (up, down, left or right) defaults to right, and `target` is the
+//! score that completes the level in a campaign.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::game::{check_board_size, Direction, Point};
+
+/// The levels shipped with the game, as `(name, map)` pairs.
+const BUNDLED: [(&str, &str); 5] = [
+    ("pillars", include_str!("../levels/pillars.txt")),
+    ("crossroads", include_str!("../levels/crossroads.txt")),
+    ("tunnels", include_str!("../levels/tunnels.txt")),
+    ("rooms", include_str!("../levels/rooms.txt")),
+    ("spiral", include_str!("../levels/spiral.txt")),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Level {
+    pub name: String,
+    /// Board width, border included.
+    pub width: i32,
+    /// Board height, border included.
+    pub height: i32,
+    /// Wall cells inside the border.
+    pub obstacles: HashSet<Point>,
+    pub start: Point,
+    pub direction: Direction,
+    /// Score that completes the level in a campaign.
+    pub target: Option<i32>,
+}
+
+#[derive(Debug)]
+pub enum LevelError {
+    Io(io::Error),
+    /// No bundled level has this name.
+    Unknown(String),
+    /// The map could not be parsed (1-based line number).
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LevelError::Io(err) => write!(f, "{}", err),
+            LevelError::Unknown(name) => write!(f, "no level named `{}`", name),
+            LevelError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for LevelError {}
+
+impl From<io::Error> for LevelError {
+    fn from(err: io::Error) -> Self {
+        LevelError::Io(err)
+    }
+}
+
+impl Level {
+    /// The names of the bundled levels, in order.
+    pub fn bundled_names() -> impl Iterator<Item = &'static str> {
+        BUNDLED.iter().map(|(name, _)| *name)
+    }
+
+    /// Loads a bundled level by name.
+    pub fn bundled(name: &str) -> Result<Level, LevelError> {
+        let (_, map) = BUNDLED
+            .iter()
+            .find(|(bundled, _)| bundled.eq_ignore_ascii_case(name))
+            .ok_or_else(|| LevelError::Unknown(name.to_string()))?;
+        map.parse()
+    }
+
+    /// Loads a level from a map file.
+    pub fn load(path: &Path) -> Result<Level, LevelError> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    /// Loads a bundled level if one has this name, otherwise the map file at
+    /// that path.
+    pub fn find(name_or_path: &str) -> Result<Level, LevelError> {
+        match Level::bundled(name_or_path) {
+            Err(LevelError::Unknown(_)) => Level::load(Path::new(name_or_path)),
+            result => result,
+        }
+    }
+
+    /// Whether `point` is a wall, border included.
+    pub fn is_wall(&self, point: Point) -> bool {
+        point.x <= 0
+            || point.y <= 0
+            || point.x >= self.width - 1
+            || point.y >= self.height - 1
+            || self.obstacles.contains(&point)
+    }
+}
+
+impl std::str::FromStr for Level {
+    type Err = LevelError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut name = String::from("Untitled");
+        let mut direction = Direction::Right;
+        let mut target = None;
+        let mut rows: Vec<(usize, &str)> = Vec::new();
+        let mut map_ended = false;
+
+        for (i, line) in s.lines().enumerate() {
+            let line_no = i + 1;
+            let line = line.trim_end();
+            let err = |message: String| LevelError::Parse { line: line_no, message };
+
+            if rows.is_empty() {
+                if line.is_empty() {
+                    continue;
+                }
+                if let Some((key, value)) = line.split_once(':') {
+                    let value = value.trim();
+                    match key.trim() {
+                        "name" => name = value.to_string(),
+                        "direction" => {
+                            direction = parse_direction(value)
+                                .ok_or_else(|| err(format!("unknown direction `{}`", value)))?
+                        }
+                        "target" => {
+                            target = Some(
+                                value
+                                    .parse()
+                                    .map_err(|_| err(format!("invalid target `{}`", value)))?,
+                            )
+                        }
+                        other => return Err(err(format!("unknown metadata `{}`", other))),
+                    }
+                    continue;
+                }
+            } else if line.is_empty() {
+                // A blank line ends the map, and only more blank lines may follow
+                map_ended = true;
+                continue;
+            } else if map_ended {
+                return Err(err("unexpected text after the map".to_string()));
+            }
+            rows.push((line_no, line));
+        }
+
+        let parse_err = |line: usize, message: &str| LevelError::Parse {
+            line,
+            message: message.to_string(),
+        };
+        let last_line = rows.last().map_or(1, |(line_no, _)| *line_no);
+        let width = rows.first().map_or(0, |(_, row)| row.chars().count()) as i32;
+        let height = rows.len() as i32;
+        check_board_size(width, height).map_err(|message| parse_err(last_line, &message))?;
+
+        let mut obstacles = HashSet::new();
+        let mut start = None;
+        for (y, (line_no, row)) in rows.iter().enumerate() {
+            if row.chars().count() as i32 != width {
+                return Err(parse_err(*line_no, "all map rows must be the same width"));
+            }
+            for (x, c) in row.chars().enumerate() {
+                let point = Point {
+                    x: x as i32,
+                    y: y as i32,
+                };
+                let border = point.x == 0 || point.y == 0 || point.x == width - 1 || point.y == height - 1;
+                match c {
+                    '#' if border => {}
+                    '#' => {
+                        obstacles.insert(point);
+                    }
+                    _ if border => return Err(parse_err(*line_no, "the border must be all walls")),
+                    '.' => {}
+                    'S' if start.is_none() => start = Some(point),
+                    'S' => return Err(parse_err(*line_no, "the map has more than one `S`")),
+                    other => {
+                        return Err(parse_err(*line_no, &format!("unexpected character `{}`", other)))
+                    }
+                }
+            }
+        }
+
+        let start = start.ok_or_else(|| parse_err(last_line, "the map has no `S` start"))?;
+        let floor = (width - 2) * (height - 2) - obstacles.len() as i32;
+        if floor < 2 {
+            return Err(parse_err(last_line, "the map has no floor left for the food"));
+        }
+
+        Ok(Level {
+            name,
+            width,
+            height,
+            obstacles,
+            start,
+            direction,
+            target,
+        })
+    }
+}
+
+impl fmt::Display for Level {
+    /// Writes the level back out in the map file format.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "name: {}", self.name)?;
+        writeln!(f, "direction: {}", direction_name(self.direction))?;
+        if let Some(target) = self.target {
+            writeln!(f, "target: {}", target)?;
+        }
+        writeln!(f)?;
+        for y in 0..self.height {
+            let row: String = (0..self.width)
+                .map(|x| {
+                    let point = Point { x, y };
+                    if point == self.start {
+                        'S'
+                    } else if self.is_wall(point) {
+                        '#'
+                    } else {
+                        '.'
+                    }
+                })
+                .collect();
+            writeln!(f, "{}", row)?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_direction(s: &str) -> Option<Direction> {
+    match s.to_ascii_lowercase().as_str() {
+        "up" => Some(Direction::Up),
+        "down" => Some(Direction::Down),
+        "left" => Some(Direction::Left),
+        "right" => Some(Direction::Right),
+        _ => None,
+    }
+}
+
+fn direction_name(direction: Direction) -> &'static str {
+    match direction {
+        Direction::Up => "up",
+        Direction::Down => "down",
+        Direction::Left => "left",
+        Direction::Right => "right",
+    }
+}
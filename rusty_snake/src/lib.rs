@@ -0,0 +1,19 @@
+//! Rusty Snake: a terminal snake game.
+//!
+//! The game rules live in [`game`] and have no terminal dependencies, so they
+//! can be driven by the interactive front end in `main.rs` or by other tools.
+
+pub mod autopilot;
+pub mod bot;
+pub mod campaign;
+pub mod controller;
+pub mod env;
+pub mod game;
+pub mod highscore;
+pub mod keys;
+pub mod level;
+pub mod net;
+pub mod profile;
+pub mod replay;
+pub mod theme;
+pub mod tournament;
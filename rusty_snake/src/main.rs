@@ -1,3 +1,12 @@
+mod cli;
+mod menu;
+mod netplay;
+mod playback;
+mod renderer;
+mod tty;
+
+use clap::Parser;
+use cli::{Cli, Command};
 use crossterm::{
     cursor,
     event::{self, Event, KeyCode},
@@ -5,425 +14,876 @@ use crossterm::{
     style::{Color, Print, SetForegroundColor},
     terminal::{self, ClearType},
 };
-use rand::Rng;
-use std::collections::{HashSet, VecDeque};
+use renderer::{Layout, Renderer};
+use tty::TerminalGuard;
+use rusty_snake::bot::BotController;
+use rusty_snake::campaign::Campaign;
+use rusty_snake::controller::{self, AutopilotController, KeyboardController, SnakeController};
+use rusty_snake::game::{GameEvent, GameState, Settings};
+use rusty_snake::highscore::{self, HighScores};
+use rusty_snake::keys::{Action, KeyBindings};
+use rusty_snake::net::Client;
+use rusty_snake::replay::{self, Recorder, Replay};
+use rusty_snake::theme::{Theme, Themes};
+use rusty_snake::tournament::{self, Entrant, Summary};
 use std::io::{stdout, Write};
+use std::net::TcpListener;
 use std::time::{Duration, Instant};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-struct Point {
-    x: i32,
-    y: i32,
+/// The player's choice of look and controls, read before the game starts.
+struct Prefs {
+    theme: Theme,
+    keys: KeyBindings,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq)]
-enum Direction {
-    Up,
-    Down,
-    Left,
-    Right,
+/// How a single game ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Quit,
+    /// A snake died; see `game_over_message`.
+    Over,
+    /// The score reached the target given to `play_game`, or the board filled up.
+    ReachedTarget,
 }
 
-impl Direction {
-    /// Returns `(dx, dy)` for each direction.
-    fn vector(self) -> (i32, i32) {
-        match self {
-            Direction::Up => (0, -1),
-            Direction::Down => (0, 1),
-            Direction::Left => (-1, 0),
-            Direction::Right => (1, 0),
+/// Who steers player 1 instead of the keyboard, if anyone.
+#[derive(Debug, Default)]
+struct Pilot {
+    /// Whether the built-in AI is steering.
+    autopilot: bool,
+    /// An external bot, which steers for the whole game.
+    bot: Option<BotController>,
+    /// Whether either has steered at all, in which case the score doesn't count.
+    used: bool,
+}
+
+/// Runs the `tournament` subcommand and prints the results to stdout.
+fn run_tournament(cli: &Cli) {
+    let Some(Command::Tournament { bots, games, max_ticks, bot_timeout, jobs, format }) = &cli.command else {
+        return;
+    };
+    let timeout = Duration::from_millis(*bot_timeout);
+    let entrants: Vec<Entrant> = bots.iter().map(|bot| Entrant::new(bot, timeout)).collect();
+    let jobs = match jobs {
+        Some(jobs) => *jobs as usize,
+        None => std::thread::available_parallelism().map_or(1, usize::from),
+    };
+
+    match tournament::run(&cli.unattended_settings(), &entrants, *games, *max_ticks, jobs) {
+        Ok(results) => {
+            let summaries: Vec<Summary> = entrants
+                .iter()
+                .zip(&results)
+                .map(|(entrant, records)| Summary::new(entrant.name(), records))
+                .collect();
+            print!("{}", tournament::report(&summaries, *format));
+        }
+        Err(err) => {
+            eprintln!("Tournament failed: {}", err);
+            std::process::exit(1);
         }
     }
 }
 
 fn main() -> crossterm::Result<()> {
+    let mut cli = Cli::parse();
+    cli.check_command();
+
+    // Load a replay up front, so errors are printed before entering raw mode
+    let replay = match &cli.replay {
+        Some(path) => match Replay::load(path) {
+            Ok(replay) => Some(replay),
+            Err(err) => {
+                eprintln!("Could not load replay {}: {}", path.display(), err);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+
+    // Likewise pick the theme...
+    let theme = match Themes::default_path().map_or(Ok(Themes::default()), |path| Themes::load(&path)) {
+        Ok(themes) => match themes.find(cli.theme.as_deref()) {
+            Ok(theme) if cli.ascii => theme.into_ascii(),
+            Ok(theme) => theme,
+            Err(err) => {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        },
+        Err(err) => {
+            eprintln!("Could not load themes: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    // ...and the keys...
+    let keys = match KeyBindings::default_path() {
+        Some(path) => KeyBindings::load(&path, cli.keys.as_deref()),
+        None => KeyBindings::preset(cli.keys.as_deref().unwrap_or("arrows")),
+    };
+    let keys = match keys {
+        Ok(keys) => keys,
+        Err(err) => {
+            eprintln!("Could not load key bindings: {}", err);
+            std::process::exit(1);
+        }
+    };
+    let prefs = Prefs { theme, keys };
+
+    // ...start the bot...
+    let bot = match &cli.bot {
+        Some(command) => match BotController::spawn(command, Duration::from_millis(cli.bot_timeout)) {
+            Ok(bot) => Some(bot),
+            Err(err) => {
+                eprintln!("{}: {}", command, err);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+
+    // ...and open the network connection before entering raw mode
+    let mut listener = None;
+    let mut client = None;
+    match &cli.command {
+        Some(Command::Host { port }) => match TcpListener::bind(("0.0.0.0", *port)) {
+            Ok(bound) => {
+                listener = Some(bound);
+                cli.players = Some(2);
+            }
+            Err(err) => {
+                eprintln!("Could not listen on port {}: {}", port, err);
+                std::process::exit(1);
+            }
+        },
+        Some(Command::Join { addr }) => {
+            println!("Connecting to {}...", addr);
+            match Client::join(addr) {
+                Ok(joined) => client = Some(joined),
+                Err(err) => {
+                    eprintln!("Could not join {}: {}", addr, err);
+                    std::process::exit(1);
+                }
+            }
+        }
+        // A tournament never touches the terminal
+        Some(Command::Tournament { .. }) => {
+            run_tournament(&cli);
+            return Ok(());
+        }
+        None => {}
+    }
+
     let mut stdout = stdout();
-    terminal::enable_raw_mode()?;
-    execute!(stdout, terminal::Clear(ClearType::All), cursor::Hide)?;
+    let farewell = {
+        // Restores the terminal however `run` ends
+        let _guard = TerminalGuard::enter(&mut stdout)?;
+        run(&mut stdout, &cli, &prefs, replay, bot, listener, client)?
+    };
 
-    // Display the splash screen
-    draw_splash_screen(&mut stdout)?;
+    // Leave the results on the normal screen, where they stay after exiting
+    for line in farewell {
+        println!("{}", line);
+    }
+    Ok(())
+}
 
-    // Clear the screen after splash
-    execute!(stdout, terminal::Clear(ClearType::All))?;
+/// Everything that happens on the game screen, from the splash screen to the
+/// final scores. Returns lines to print once the terminal is restored.
+fn run(
+    stdout: &mut std::io::Stdout,
+    cli: &Cli,
+    prefs: &Prefs,
+    replay: Option<Replay>,
+    bot: Option<BotController>,
+    listener: Option<TcpListener>,
+    client: Option<Client>,
+) -> crossterm::Result<Vec<String>> {
+    if let Some(replay) = replay {
+        playback::play_replay(stdout, &replay, prefs)?;
+        return Ok(Vec::new());
+    }
+
+    // The host picks the settings, so a joining player goes straight in
+    if let Some(client) = client {
+        let (game, message, winner) = netplay::join_game(stdout, client, prefs)?;
+        show_game_over(stdout, &game, &message)?;
+        return show_versus_results(stdout, &game, winner, &[], &prefs.theme);
+    }
+
+    if !cli.no_splash {
+        // Display the splash screen
+        draw_splash_screen(stdout)?;
 
-    // Allow the user to select the boundary size and initial speed
-    let (width, height, initial_speed) = select_game_settings(&mut stdout)?;
+        // Clear the screen after splash
+        execute!(stdout, terminal::Clear(ClearType::All), cursor::MoveTo(0, 0))?;
+    }
+
+    // Allow the user to select the boundary size, initial speed and seed
+    let (mut settings, campaign) = menu::select_game_settings(stdout, cli)?;
+
+    // Make sure the board fits in the terminal; one without a level can shrink
+    if settings.level.is_none() {
+        let room = menu::make_room(stdout, &prefs.keys, settings.width, settings.height, true)?;
+        let Some((width, height)) = room else {
+            return Ok(Vec::new());
+        };
+        settings.width = width;
+        settings.height = height;
+    }
+    // Record the speed actually played, for the replay and high scores
+    settings.speed = settings.clamped_speed();
 
     // Clear the screen after selection
     execute!(stdout, terminal::Clear(ClearType::All))?;
 
-    // Initialize snake in the center
-    let start = Point {
-        x: width / 2,
-        y: height / 2,
+    if let Some(listener) = listener {
+        let Some(host) = netplay::wait_for_client(stdout, &listener, &settings, prefs)? else {
+            return Ok(vec!["No one joined.".to_string()]);
+        };
+        let ((game, message, winner), replay) = netplay::host_game(stdout, &settings, host, prefs)?;
+        show_game_over(stdout, &game, &message)?;
+        let notes = [save_replay(&game, &replay)];
+        return show_versus_results(stdout, &game, winner, &notes, &prefs.theme);
+    }
+
+    // Lines to show on the final screen
+    let mut notes = Vec::new();
+    let mut pilot = Pilot {
+        autopilot: cli.autopilot,
+        bot,
+        used: false,
     };
-    let mut snake = VecDeque::new();
-    snake.push_back(start);
 
-    // Keep a set of positions for quick collision checks
-    let mut snake_positions = HashSet::new();
-    snake_positions.insert(start);
+    // A campaign that was quit can be resumed, so its score is not final yet
+    let (key, score, is_final) = match campaign {
+        Some(campaign) => {
+            let (score, ended) = play_campaign(stdout, &settings, campaign, &mut pilot, &mut notes, prefs)?;
+            (HighScores::campaign_key(&settings), score, ended)
+        }
+        None => {
+            let (game, outcome, replay) = play_game(stdout, &settings, None, None, &mut pilot, prefs)?;
+            let message = match outcome {
+                Outcome::Quit => "You quit!".to_string(),
+                _ => game_over_message(&game),
+            };
+            show_game_over(stdout, &game, &message)?;
+            notes.push(save_replay(&game, &replay));
 
-    // Random initial food
-    let mut food = Point {
-        x: 15.min(width - 2),
-        y: 15.min(height - 2),
+            // Two-player games have a winner rather than a high score
+            if game.snakes.len() > 1 {
+                return show_versus_results(stdout, &game, game.winner(), &notes, &prefs.theme);
+            }
+            (HighScores::key(&settings), game.snakes[0].score, true)
+        }
     };
 
-    // Start moving to the Right by default
-    let mut direction = Direction::Right;
-    let mut next_direction = direction;
-    let mut last_instant = Instant::now();
-    let mut score = 0;
-    let mut paused = false;
-    let mut speed = initial_speed; // Start with the selected speed
+    // Scores the AI or a bot helped with aren't comparable with human play
+    let is_final = is_final && !pilot.used;
+    if let Some(reason) = pilot.bot.as_ref().and_then(BotController::forfeit_reason) {
+        notes.push(format!("The bot forfeited: {}", reason));
+    }
+    if pilot.used {
+        notes.push("The autopilot or a bot steered, so this score is not recorded.".to_string());
+    }
 
-    // We'll store the final game-over message here
-    let game_over_message: &str;
+    // Clear the screen and show the final score
+    execute!(stdout, terminal::Clear(ClearType::All), cursor::MoveTo(0, 0))?;
+    execute!(stdout, Print(format!("Final Score: {}", score)))?;
+
+    // Record the score if it made the high-score table
+    let path = HighScores::default_path();
+    let mut high_scores = path.as_deref().map(HighScores::load).unwrap_or_default();
+    let mut rank = None;
+    if is_final && high_scores.qualifies(&key, score) {
+        let name = menu::prompt_player_name(stdout, 2)?;
+        let entry = highscore::Entry {
+            name,
+            score,
+            seed: settings.seed,
+        };
+        rank = high_scores.insert(&key, entry);
+        if let Some(path) = &path {
+            if let Err(err) = high_scores.save(path) {
+                notes.push(format!("Could not save high scores: {}", err));
+            }
+        }
+        execute!(
+            stdout,
+            terminal::Clear(ClearType::All),
+            cursor::MoveTo(0, 0),
+            Print(format!("Final Score: {}", score))
+        )?;
+    }
+
+    // Show the high scores and anything else worth knowing
+    let mut row = draw_high_scores(stdout, &key, &high_scores, rank, 2)?;
+    for note in &notes {
+        execute!(stdout, cursor::MoveTo(0, row + 1), Print(note))?;
+        row += 1;
+    }
+    execute!(stdout, cursor::MoveTo(0, row + 1), Print("Press Enter to exit..."))?;
+    wait_for_enter()?;
+
+    let mut farewell = vec![format!("Final Score: {}", score)];
+    farewell.extend(notes);
+    farewell.push("Thank you for playing!".to_string());
+    Ok(farewell)
+}
 
-    // Draw initial walls and initial status
-    draw_score(&mut stdout, score, speed)?;
-    draw_walls(&mut stdout, width, height)?;
+/// Runs one game until it ends, returning the final state, how it ended and
+/// its replay.
+///
+/// The game stops early once the score reaches `target`, if given. `banner`
+/// is shown on the line below the board. The autopilot key turns the autopilot
+/// on and off, unless a bot is steering. Restarting starts over with the same
+/// settings, except in a campaign, where each level only gets one go per life.
+fn play_game(
+    stdout: &mut std::io::Stdout,
+    settings: &Settings,
+    target: Option<i32>,
+    banner: Option<&str>,
+    pilot: &mut Pilot,
+    prefs: &Prefs,
+) -> crossterm::Result<(GameState, Outcome, Replay)> {
+    let mut game = GameState::new(settings);
+    let mut recorder = Recorder::new(settings, &game);
+    let mut last_instant = Instant::now();
+    let mut keyboards = vec![KeyboardController::new(); game.snakes.len()];
+    let mut ai = AutopilotController;
+    let mut renderer = Renderer::new(&game, &prefs.theme);
+    // Whether the help is showing, and if so whether it paused the game
+    let mut help: Option<bool> = None;
+
+    // A level's board can't shrink, so wait for the terminal to fit it
+    if menu::make_room(stdout, &prefs.keys, game.width, game.height, false)?.is_none() {
+        let replay = recorder.finish(&game);
+        return Ok((game, Outcome::Quit, replay));
+    }
+
+    // Draw the initial status; the board is drawn with the first frame
+    draw_game_screen(stdout, &game, banner, pilot, &prefs.keys)?;
 
     // Label the loop so we can break out with `break 'game_loop;`
-    'game_loop: loop {
+    let outcome = 'game_loop: loop {
         //
         // 1) Drain all pending key events in a *while* loop
         //
         while event::poll(Duration::from_millis(0))? {
             // Read the event
             match event::read()? {
-                Event::Key(key_event) => {
-                    match key_event.code {
-                        KeyCode::Char('q') => {
-                            // Quit the game
-                            game_over_message = "You quit!";
-                            break 'game_loop;
-                        }
-                        KeyCode::Char(' ') => {
-                            // Pause/unpause
-                            paused = !paused;
-                        }
-                        KeyCode::Char('+') => {
-                            // Speed up
-                            if speed > 50 {
-                                speed -= 50;
-                                draw_score(&mut stdout, score, speed)?;
-                            }
+                Event::Resize(..) => {
+                    // Pause, since the board has moved, and draw it all again
+                    if !game.is_paused() {
+                        game.toggle_pause();
+                    }
+                    // The terminal may have shrunk below the board, so wait until it fits
+                    if menu::make_room(stdout, &prefs.keys, game.width, game.height, false)?.is_none() {
+                        break 'game_loop Outcome::Quit;
+                    }
+                    match help {
+                        Some(_) => draw_help(stdout, &prefs.keys, keyboards.len(), target.is_none())?,
+                        None => draw_game_screen(stdout, &game, banner, pilot, &prefs.keys)?,
+                    }
+                    renderer.invalidate();
+                }
+                Event::Key(key_event) if tty::is_interrupt(&key_event) => {
+                    break 'game_loop Outcome::Quit;
+                }
+                Event::Key(_) if help.is_some() => {
+                    // Any key closes the help, and resumes the game if the help paused it
+                    if help.take() == Some(true) {
+                        game.toggle_pause();
+                    }
+                    draw_game_screen(stdout, &game, banner, pilot, &prefs.keys)?;
+                    renderer.invalidate();
+                }
+                Event::Key(key_event) => match prefs.keys.action(key_event.code) {
+                    Some(Action::Quit) => {
+                        // Quit the game
+                        break 'game_loop Outcome::Quit;
+                    }
+                    Some(Action::Pause) => {
+                        // Pause/unpause
+                        game.toggle_pause();
+                    }
+                    Some(Action::Autopilot) if pilot.bot.is_none() => {
+                        // Hand steering to the AI, or take it back
+                        pilot.autopilot = !pilot.autopilot;
+                        draw_pilot(stdout, &game, pilot, &prefs.keys)?;
+                    }
+                    Some(Action::Faster) => {
+                        // Speed up
+                        game.faster();
+                        recorder.observe(&game);
+                        draw_score(stdout, &game)?;
+                    }
+                    Some(Action::Slower) => {
+                        // Slow down
+                        game.slower();
+                        recorder.observe(&game);
+                        draw_score(stdout, &game)?;
+                    }
+                    Some(Action::Restart) if target.is_none() => {
+                        // Start over, forgetting the game so far
+                        game = GameState::new(settings);
+                        recorder = Recorder::new(settings, &game);
+                        keyboards.iter_mut().for_each(KeyboardController::clear);
+                        last_instant = Instant::now();
+                        draw_game_screen(stdout, &game, banner, pilot, &prefs.keys)?;
+                        renderer.invalidate();
+                    }
+                    Some(Action::Help) => {
+                        // Show the bindings over the whole screen, pausing meanwhile
+                        let pausing = !game.is_paused();
+                        if pausing {
+                            game.toggle_pause();
                         }
-                        KeyCode::Char('-') => {
-                            // Slow down
-                            if speed < 500 {
-                                speed += 50;
-                                draw_score(&mut stdout, score, speed)?;
+                        help = Some(pausing);
+                        draw_help(stdout, &prefs.keys, keyboards.len(), target.is_none())?;
+                    }
+                    // Direction changes, applied on the next tick
+                    Some(action) => {
+                        if let Some((player, direction)) = action.turn() {
+                            // Player 2's keys do nothing in a one-player game
+                            if let Some(keyboard) = keyboards.get_mut(player) {
+                                keyboard.press(direction);
                             }
                         }
-                        // Direction changes, avoiding reverse
-                        KeyCode::Up if direction != Direction::Down => {
-                            next_direction = Direction::Up;
-                        }
-                        KeyCode::Down if direction != Direction::Up => {
-                            next_direction = Direction::Down;
-                        }
-                        KeyCode::Left if direction != Direction::Right => {
-                            next_direction = Direction::Left;
-                        }
-                        KeyCode::Right if direction != Direction::Left => {
-                            next_direction = Direction::Right;
-                        }
-                        _ => {}
                     }
-                }
+                    None => {}
+                },
                 _ => {}
             }
         }
 
         //
-        // 2) If the game is paused, just sleep a bit and skip movement
+        // 2) Check if it's time to move the snake, unless the game is paused
         //
-        if paused {
-            std::thread::sleep(Duration::from_millis(10));
-            continue;
+        if !game.is_paused() && last_instant.elapsed() >= Duration::from_millis(game.speed) {
+            last_instant = Instant::now();
+
+            // Ask each snake's controller where to go; a bot or the AI stands
+            // in for player 1's keyboard
+            pilot.used |= pilot.autopilot || pilot.bot.is_some();
+            let (first, others) = keyboards.split_first_mut().expect("a game has at least one snake");
+            let mut controllers: Vec<&mut dyn SnakeController> = Vec::new();
+            match (&mut pilot.bot, pilot.autopilot) {
+                (Some(bot), _) => {
+                    first.clear();
+                    controllers.push(bot);
+                }
+                (None, true) => {
+                    first.clear();
+                    controllers.push(&mut ai);
+                }
+                (None, false) => controllers.push(first),
+            }
+            controllers.extend(others.iter_mut().map(|keyboard| keyboard as &mut dyn SnakeController));
+            let events = controller::step(&mut game, &mut controllers);
+            recorder.observe(&game);
+            draw_step_events(stdout, &game, &events)?;
+            // Filling the board completes a level, whatever its target
+            let reached = target.is_some_and(|target| game.snakes[0].score >= target || game.is_won());
+            if reached || game.is_over() {
+                // Show the final move, such as the last food eaten on a full board
+                renderer.draw(&game);
+                renderer.flush(stdout, &Layout::new(&game)?)?;
+                break 'game_loop if reached { Outcome::ReachedTarget } else { Outcome::Over };
+            }
         }
 
         //
-        // 3) Check if it's time to move the snake
+        // 3) Render the changes since the last frame, unless the help covers the board
         //
-        if last_instant.elapsed() >= Duration::from_millis(speed) {
-            last_instant = Instant::now();
-
-            // Update direction from the queued next_direction
-            direction = next_direction;
+        if help.is_none() {
+            renderer.draw(&game);
+            renderer.flush(stdout, &Layout::new(&game)?)?;
+        }
 
-            // Calculate new head position
-            let (dx, dy) = direction.vector();
-            let head = snake.back().unwrap();
-            let new_head = Point {
-                x: head.x + dx,
-                y: head.y + dy,
-            };
+        //
+        // 4) Small sleep to avoid busy-looping at 100% CPU
+        //
+        std::thread::sleep(Duration::from_millis(10));
+    };
 
-            // Check collisions: walls
-            if new_head.x < 1 || new_head.x >= width - 1 || new_head.y < 1 || new_head.y >= height - 1 {
-                game_over_message = "Game Over! You hit the wall!";
-                break 'game_loop;
-            }
-            // Check collisions: self
-            if snake_positions.contains(&new_head) {
-                game_over_message = "Game Over! You hit yourself!";
-                break 'game_loop;
-            }
+    let replay = recorder.finish(&game);
+    Ok((game, outcome, replay))
+}
 
-            // Update snake
-            snake.push_back(new_head);
-            snake_positions.insert(new_head);
-
-            if new_head == food {
-                // Ate the food
-                score += 1;
-                draw_score(&mut stdout, score, speed)?;
-
-                // Generate new food, avoiding the snake
-                let mut rng = rand::thread_rng();
-                loop {
-                    let new_food = Point {
-                        x: rng.gen_range(1..width - 1),
-                        y: rng.gen_range(1..height - 1),
-                    };
-                    if !snake_positions.contains(&new_food) {
-                        food = new_food;
-                        break;
-                    }
+/// Plays the campaign from `campaign`'s current level until it is finished,
+/// the lives run out or the player quits. Returns the total score and whether
+/// the run ended (rather than being quit).
+fn play_campaign(
+    stdout: &mut std::io::Stdout,
+    settings: &Settings,
+    mut campaign: Campaign,
+    pilot: &mut Pilot,
+    notes: &mut Vec<String>,
+    prefs: &Prefs,
+) -> crossterm::Result<(i32, bool)> {
+    let path = Campaign::default_path();
+    let mut settings = settings.clone();
+
+    let last_replay = loop {
+        let level = campaign.current_level();
+        let target = campaign.target();
+        let banner = format!(
+            "Campaign: Level {}/{} {} | Target: {} | Lives: {} | Total: {}",
+            campaign.level + 1,
+            Campaign::level_count(),
+            level.name,
+            target,
+            campaign.lives,
+            campaign.total_score
+        );
+        settings.width = level.width;
+        settings.height = level.height;
+        settings.level = Some(level);
+
+        let (game, outcome, replay) = play_game(stdout, &settings, Some(target), Some(&banner), pilot, prefs)?;
+        let replay_note = save_replay(&game, &replay);
+
+        let score = game.snakes[0].score;
+        let message = match outcome {
+            // Quitting mid-level costs a life, so it can't be used to retry a level for free
+            Outcome::Quit if game.tick > 0 && !campaign.lose_life() => "You quit on your last life!".to_string(),
+            Outcome::Quit => {
+                notes.push("Campaign progress saved; choose Campaign again to resume.".to_string());
+                if game.tick > 0 {
+                    "You quit! That cost a life; your progress is saved.".to_string()
+                } else {
+                    "You quit! Your progress is saved.".to_string()
                 }
+            }
+            Outcome::ReachedTarget if campaign.complete_level(score) => "Level complete! On to the next level.".to_string(),
+            Outcome::ReachedTarget => "You completed the campaign!".to_string(),
+            Outcome::Over if campaign.lose_life() => game_over_message(&game),
+            Outcome::Over => "Game Over! You are out of lives.".to_string(),
+        };
+
+        // Keep progress for an unfinished run, and forget it once the run is over
+        if let Some(path) = &path {
+            let result = if campaign.is_finished() || campaign.is_over() {
+                Campaign::clear(path)
             } else {
-                // Normal movement: pop tail
-                let tail = snake.pop_front().unwrap();
-                snake_positions.remove(&tail);
-
-                // Clear the old tail position from screen
-                execute!(
-                    stdout,
-                    cursor::MoveTo(tail.x as u16, (tail.y + 1) as u16),
-                    Print(" ")
-                )?;
+                campaign.save(path)
+            };
+            if let Err(err) = result {
+                notes.push(format!("Could not save campaign progress: {}", err));
             }
         }
 
-        //
-        // 4) Render the snake and the food
-        //
-        render_snake_and_food(&mut stdout, &snake, &food)?;
-        stdout.flush()?;
+        show_game_over(stdout, &game, &message)?;
+        if outcome == Outcome::Quit || campaign.is_finished() || campaign.is_over() {
+            break replay_note;
+        }
+    };
 
-        //
-        // 5) Small sleep to avoid busy-looping at 100% CPU
-        //
-        std::thread::sleep(Duration::from_millis(10));
-    }
+    notes.push(last_replay);
+    Ok((campaign.total_score, campaign.is_finished() || campaign.is_over()))
+}
 
-    //
-    // The game is over (via break), display the final message
-    //
+/// Clears the screen and draws everything around the board: the score, the
+/// banner on the line below the board, and who is steering.
+fn draw_game_screen(
+    stdout: &mut std::io::Stdout,
+    game: &GameState,
+    banner: Option<&str>,
+    pilot: &Pilot,
+    keys: &KeyBindings,
+) -> crossterm::Result<()> {
+    execute!(stdout, terminal::Clear(ClearType::All))?;
+    draw_score(stdout, game)?;
+    let layout = Layout::new(game)?;
+    if let Some(banner) = banner {
+        execute!(stdout, layout.below(0), SetForegroundColor(Color::White), Print(banner))?;
+    }
     execute!(
         stdout,
-        cursor::MoveTo(0, height as u16 + 2),
+        layout.below(2),
         SetForegroundColor(Color::White),
-        Print(format!("{}\nPress Enter to continue...", game_over_message))
+        Print(format!("Press {} for help", keys.describe(Action::Help)))
     )?;
-    wait_for_enter()?;
+    draw_pilot(stdout, game, pilot, keys)
+}
 
-    // Clear the screen and show the final score
-    execute!(stdout, terminal::Clear(ClearType::All), cursor::MoveTo(0, 0))?;
+/// Lists the key bindings in place of the game screen. Player 2's keys are
+/// only listed for two players, and restarting only when it is allowed.
+fn draw_help(
+    stdout: &mut std::io::Stdout,
+    keys: &KeyBindings,
+    players: usize,
+    can_restart: bool,
+) -> crossterm::Result<()> {
     execute!(
         stdout,
-        Print(format!("Final Score: {}\n", score)),
-        Print("Thank you for playing!\n")
+        terminal::Clear(ClearType::All),
+        cursor::MoveTo(0, 0),
+        SetForegroundColor(Color::Yellow),
+        Print("Keys"),
+        SetForegroundColor(Color::White)
+    )?;
+    let shown = Action::ALL.into_iter().filter(|&action| match action {
+        Action::Restart => can_restart,
+        _ => action.turn().is_none_or(|(player, _)| player < players),
+    });
+    let mut row = 2;
+    for action in shown {
+        execute!(
+            stdout,
+            cursor::MoveTo(0, row),
+            Print(format!("{:<18}{}", action.description(), keys.describe(action)))
+        )?;
+        row += 1;
+    }
+    execute!(
+        stdout,
+        cursor::MoveTo(0, row + 1),
+        Print("Ctrl+C always quits. Press any key to go back.")
     )?;
-
-    terminal::disable_raw_mode()?;
     Ok(())
 }
 
-/// Show the menu to select boundary size and speed, returning `(width, height, speed_ms_per_tick)`.
-fn select_game_settings(stdout: &mut std::io::Stdout) -> crossterm::Result<(i32, i32, u64)> {
+/// Shows whether a bot or the autopilot is steering, two lines below the board.
+fn draw_pilot(
+    stdout: &mut std::io::Stdout,
+    game: &GameState,
+    pilot: &Pilot,
+    keys: &KeyBindings,
+) -> crossterm::Result<()> {
     execute!(
         stdout,
-        cursor::MoveTo(0, 0),
-        SetForegroundColor(Color::White),
-        Print("Select Boundary Size:\n"),
-        Print("1. Small (20x10)\n2. Medium (40x20)\n3. Large (60x30)\n"),
-        Print("Press 1, 2, or 3 to choose: ")
+        Layout::new(game)?.below(1),
+        terminal::Clear(ClearType::CurrentLine),
+        SetForegroundColor(Color::White)
     )?;
-    stdout.flush()?;
-
-    let width;
-    let height;
+    if pilot.bot.is_some() {
+        execute!(stdout, Print("A bot is steering player 1"))?;
+    } else if pilot.autopilot {
+        execute!(
+            stdout,
+            Print(format!("Autopilot is steering ({} to take over)", keys.describe(Action::Autopilot)))
+        )?;
+    }
+    Ok(())
+}
 
-    // Get boundary size selection
-    loop {
-        if event::poll(Duration::from_millis(100))? {
-            if let Event::Key(key_event) = event::read()? {
-                match key_event.code {
-                    KeyCode::Char('1') => {
-                        width = 20;
-                        height = 10;
-                        execute!(
-                            stdout,
-                            cursor::MoveToNextLine(1),
-                            Print("You selected Small (20x10)\n")
-                        )?;
-                        break;
-                    }
-                    KeyCode::Char('2') => {
-                        width = 40;
-                        height = 20;
-                        execute!(
-                            stdout,
-                            cursor::MoveToNextLine(1),
-                            Print("You selected Medium (40x20)\n")
-                        )?;
-                        break;
-                    }
-                    KeyCode::Char('3') => {
-                        width = 60;
-                        height = 30;
-                        execute!(
-                            stdout,
-                            cursor::MoveToNextLine(1),
-                            Print("You selected Large (60x30)\n")
-                        )?;
-                        break;
-                    }
-                    _ => {}
-                }
-            }
-        }
+/// Describes how a finished game ended: the cause of death for one player, or
+/// who won for two.
+fn game_over_message(game: &GameState) -> String {
+    if game.is_won() {
+        return match (game.snakes.len(), game.winner()) {
+            (1, _) => "You win! You filled the board!".to_string(),
+            (_, Some(player)) => format!("The board is full! Player {} wins!", player + 1),
+            (_, None) => "The board is full! It's a draw!".to_string(),
+        };
+    }
+    if let [snake] = game.snakes.as_slice() {
+        return snake.death.map_or("Game Over!", |cause| cause.message()).to_string();
     }
 
-    let speed;
+    let deaths: Vec<String> = game
+        .snakes
+        .iter()
+        .enumerate()
+        .filter_map(|(player, snake)| {
+            snake
+                .death
+                .map(|cause| format!("Player {} {}.", player + 1, cause.description()))
+        })
+        .collect();
+    let result = match game.winner() {
+        Some(player) => format!("Player {} wins!", player + 1),
+        None => "It's a draw!".to_string(),
+    };
+    format!("{} {}", deaths.join(" "), result)
+}
 
-    // Prompt for difficulty selection
+/// Shows each player's score and the winner (0-based) after a two-player game.
+fn show_versus_results(
+    stdout: &mut std::io::Stdout,
+    game: &GameState,
+    winner: Option<usize>,
+    notes: &[String],
+    theme: &Theme,
+) -> crossterm::Result<Vec<String>> {
+    execute!(stdout, terminal::Clear(ClearType::All))?;
+    let mut row = 0;
+    let mut farewell = Vec::new();
+    for (player, snake) in game.snakes.iter().enumerate() {
+        let (head_color, _) = theme.snake_colors(player);
+        let line = format!("Player {}: {}", player + 1, snake.score);
+        execute!(
+            stdout,
+            cursor::MoveTo(0, row),
+            SetForegroundColor(head_color),
+            Print(&line)
+        )?;
+        farewell.push(line);
+        row += 1;
+    }
+    let result = match winner {
+        Some(player) => format!("Player {} wins!", player + 1),
+        None => "It's a draw!".to_string(),
+    };
     execute!(
         stdout,
-        cursor::MoveToNextLine(2),
-        Print("Select Difficulty:\n"),
-        Print("1. Easy (300ms per tick)\n2. Normal (200ms per tick)\n3. Hard (100ms per tick)\n"),
-        Print("Press 1, 2, or 3 to choose: ")
+        cursor::MoveTo(0, row + 1),
+        SetForegroundColor(Color::White),
+        Print(&result)
     )?;
-    stdout.flush()?;
-
-    // Get difficulty selection
-    loop {
-        if event::poll(Duration::from_millis(100))? {
-            if let Event::Key(key_event) = event::read()? {
-                match key_event.code {
-                    KeyCode::Char('1') => {
-                        speed = 300;
-                        execute!(
-                            stdout,
-                            cursor::MoveToNextLine(1),
-                            Print("You selected Easy (300ms per tick)\n")
-                        )?;
-                        break;
-                    }
-                    KeyCode::Char('2') => {
-                        speed = 200;
-                        execute!(
-                            stdout,
-                            cursor::MoveToNextLine(1),
-                            Print("You selected Normal (200ms per tick)\n")
-                        )?;
-                        break;
-                    }
-                    KeyCode::Char('3') => {
-                        speed = 100;
-                        execute!(
-                            stdout,
-                            cursor::MoveToNextLine(1),
-                            Print("You selected Hard (100ms per tick)\n")
-                        )?;
-                        break;
-                    }
-                    _ => {}
-                }
-            }
-        }
+    farewell.push(result);
+    row += 2;
+    for note in notes {
+        execute!(stdout, cursor::MoveTo(0, row + 1), Print(note))?;
+        row += 1;
     }
+    execute!(stdout, cursor::MoveTo(0, row + 1), Print("Press Enter to exit..."))?;
+    wait_for_enter()?;
 
-    // Wait a moment before clearing the screen
-    std::thread::sleep(Duration::from_millis(1000));
-    execute!(stdout, terminal::Clear(ClearType::All))?;
+    farewell.extend(notes.iter().cloned());
+    farewell.push("Thank you for playing!".to_string());
+    Ok(farewell)
+}
 
-    Ok((width, height, speed))
+/// Shows `message` below the board and waits for Enter. A game won by
+/// filling the board also gets a victory banner across the board.
+fn show_game_over(stdout: &mut std::io::Stdout, game: &GameState, message: &str) -> crossterm::Result<()> {
+    if game.is_won() {
+        draw_victory(stdout, game)?;
+    }
+    let layout = Layout::new(game)?;
+    execute!(
+        stdout,
+        layout.below(1),
+        SetForegroundColor(Color::White),
+        Print(message),
+        layout.below(2),
+        Print("Press Enter to continue...")
+    )?;
+    wait_for_enter()
 }
 
-/// Draws the boundary walls using `#`.
-fn draw_walls(stdout: &mut std::io::Stdout, width: i32, height: i32) -> crossterm::Result<()> {
-    for y in 0..height {
-        for x in 0..width {
-            if y == 0 || y == height - 1 || x == 0 || x == width - 1 {
-                execute!(
-                    stdout,
-                    cursor::MoveTo(x as u16, (y + 1) as u16),
-                    Print("#")
-                )?;
-            }
-        }
+/// Draws a banner across the middle of a board the snakes have filled.
+fn draw_victory(stdout: &mut std::io::Stdout, game: &GameState) -> crossterm::Result<()> {
+    let layout = Layout::new(game)?;
+    let title = if game.snakes.len() == 1 { "YOU WIN!" } else { "BOARD FULL!" };
+    let lines = [String::new(), format!("  {}  ", title), String::new()];
+    let width = lines[1].len() as u16;
+    let left = layout.left + (game.width as u16).saturating_sub(width) / 2;
+    let top = layout.top + (game.height as u16).saturating_sub(lines.len() as u16) / 2;
+    for (row, line) in lines.iter().enumerate() {
+        execute!(
+            stdout,
+            cursor::MoveTo(left, top + row as u16),
+            SetForegroundColor(Color::Yellow),
+            Print(format!("{:^width$}", line, width = width as usize))
+        )?;
     }
+    execute!(stdout, SetForegroundColor(Color::White))?;
     Ok(())
 }
 
-/// Renders the snake and the food in one pass.
-fn render_snake_and_food(
+/// Saves the replay of a finished game, returning a line for the final screen.
+fn save_replay(game: &GameState, replay: &Replay) -> String {
+    let Some(path) = replay::default_dir().map(|dir| dir.join(replay::file_name(game))) else {
+        return "Could not save replay: no data directory".to_string();
+    };
+    match replay.save(&path) {
+        Ok(()) => format!("Replay saved to {}", path.display()),
+        Err(err) => format!("Could not save replay: {}", err),
+    }
+}
+
+/// Updates the status line for the events of one step; the board itself is
+/// redrawn by the [`Renderer`].
+fn draw_step_events(
     stdout: &mut std::io::Stdout,
-    snake: &VecDeque<Point>,
-    food: &Point,
+    game: &GameState,
+    events: &[GameEvent],
 ) -> crossterm::Result<()> {
-    // Draw the snake
-    // The last element in `snake` is the head
-    if let Some((last_idx, _)) = snake.iter().enumerate().last() {
-        for (i, segment) in snake.iter().enumerate() {
-            if i == last_idx {
-                // Head
-                execute!(
-                    stdout,
-                    cursor::MoveTo(segment.x as u16, (segment.y + 1) as u16),
-                    SetForegroundColor(Color::Yellow),
-                    Print("█")
-                )?;
-            } else {
-                // Body
-                execute!(
-                    stdout,
-                    cursor::MoveTo(segment.x as u16, (segment.y + 1) as u16),
-                    SetForegroundColor(Color::Green),
-                    Print("█")
-                )?;
-            }
-        }
+    if events.iter().any(|event| matches!(event, GameEvent::AteFood { .. })) {
+        draw_score(stdout, game)?;
     }
+    Ok(())
+}
 
-    // Draw the food
+/// Draws the score (along with speed, level and seed) on the line above the board.
+fn draw_score(stdout: &mut std::io::Stdout, game: &GameState) -> crossterm::Result<()> {
+    let level = match game.level() {
+        Some(level) => format!(" | Level: {}", level),
+        None => String::new(),
+    };
     execute!(
         stdout,
-        cursor::MoveTo(food.x as u16, (food.y + 1) as u16),
-        SetForegroundColor(Color::Red),
-        Print("■")
+        Layout::new(game)?.score_line(),
+        SetForegroundColor(Color::White),
+        Print(format!(
+            "{} | Speed: {}ms{} | Seed: {} ",
+            score_text(game),
+            game.speed,
+            level,
+            game.seed
+        ))
     )?;
-
     Ok(())
 }
 
-/// Draws the score (and speed) at the top of the screen.
-fn draw_score(stdout: &mut std::io::Stdout, score: i32, speed: u64) -> crossterm::Result<()> {
+/// The score line: `Score: 3`, or `P1: 3 | P2: 5` with two players.
+fn score_text(game: &GameState) -> String {
+    if let [snake] = game.snakes.as_slice() {
+        return format!("Score: {}", snake.score);
+    }
+    game.snakes
+        .iter()
+        .enumerate()
+        .map(|(player, snake)| format!("P{}: {}", player + 1, snake.score))
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+/// Draws the high-score table `key` starting at `row`, highlighting the entry at
+/// `rank`. Returns the row after the last line drawn.
+fn draw_high_scores(
+    stdout: &mut std::io::Stdout,
+    key: &str,
+    high_scores: &HighScores,
+    rank: Option<usize>,
+    row: u16,
+) -> crossterm::Result<u16> {
     execute!(
         stdout,
-        cursor::MoveTo(0, 0),
+        cursor::MoveTo(0, row),
         SetForegroundColor(Color::White),
-        Print(format!("Score: {} | Speed: {}ms ", score, speed))
+        Print(format!("High Scores ({})", key))
     )?;
-    Ok(())
+
+    let table = high_scores.table(key);
+    if table.is_empty() {
+        execute!(stdout, cursor::MoveTo(0, row + 1), Print("  No scores yet"))?;
+        return Ok(row + 2);
+    }
+
+    for (i, entry) in table.iter().enumerate() {
+        let color = if Some(i) == rank { Color::Yellow } else { Color::White };
+        execute!(
+            stdout,
+            cursor::MoveTo(0, row + 1 + i as u16),
+            SetForegroundColor(color),
+            Print(format!(
+                "{:>3}. {:<width$} {:>5}  (seed {})",
+                i + 1,
+                entry.name,
+                entry.score,
+                entry.seed,
+                width = highscore::MAX_NAME_LEN
+            ))
+        )?;
+    }
+    execute!(stdout, SetForegroundColor(Color::White))?;
+    Ok(row + 1 + table.len() as u16)
 }
 
 /// Wait until the user presses Enter.
@@ -431,7 +891,7 @@ fn wait_for_enter() -> crossterm::Result<()> {
     loop {
         if event::poll(Duration::from_millis(100))? {
             if let Event::Key(key_event) = event::read()? {
-                if let KeyCode::Enter = key_event.code {
+                if key_event.code == KeyCode::Enter || tty::is_interrupt(&key_event) {
                     break;
                 }
             }
@@ -0,0 +1,615 @@
+use crate::cli::Cli;
+use crate::renderer;
+use crate::tty;
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode},
+    execute,
+    style::{Color, Print, SetForegroundColor},
+    terminal::{self, ClearType},
+};
+use rusty_snake::campaign::Campaign;
+use rusty_snake::game::{self, Difficulty, Settings, WallMode, MAX_BOARD_SIZE, MIN_BOARD_SIZE};
+use rusty_snake::highscore::MAX_NAME_LEN;
+use rusty_snake::keys::{Action, KeyBindings};
+use rusty_snake::level::Level;
+use rusty_snake::profile::Profile;
+use std::io::Write;
+use std::time::Duration;
+
+/// What to play on, as chosen in the boundary size menu.
+enum Board {
+    Size(i32, i32),
+    Level(Level),
+    Campaign,
+}
+
+/// Show the menus to select boundary size, level or campaign, players, walls, difficulty and seed.
+///
+/// Each question is skipped when its answer was already given on the command line.
+/// The walls are part of the board, so they default to solid when the board
+/// size was given without `--walls`. Returns the campaign to play, if one was chosen.
+pub fn select_game_settings(
+    stdout: &mut std::io::Stdout,
+    cli: &Cli,
+) -> crossterm::Result<(Settings, Option<Campaign>)> {
+    let mut prompted = false;
+
+    let board = match (cli.campaign, &cli.level, cli.board_size()) {
+        (true, _, _) => Board::Campaign,
+        (false, Some(level), _) => Board::Level(level.clone()),
+        (false, None, Some((width, height))) => Board::Size(width, height),
+        (false, None, None) => {
+            prompted = true;
+            // The campaign is single-player
+            select_board_size(stdout, cli.players.unwrap_or(1) == 1)?
+        }
+    };
+    let (width, height, level, campaign) = match board {
+        Board::Size(width, height) => (width, height, None, None),
+        Board::Level(level) => (level.width, level.height, Some(level), None),
+        Board::Campaign => {
+            // Resume saved progress without asking when started from the command line
+            let campaign = select_campaign(stdout, cli.campaign)?;
+            let level = campaign.current_level();
+            (level.width, level.height, Some(level), Some(campaign))
+        }
+    };
+    // The campaign is single-player, and a board given on the command line
+    // means a quick start, so only ask when the board menu was shown
+    let players = match cli.players.map(usize::from) {
+        Some(players) => players,
+        None if !prompted || campaign.is_some() => 1,
+        None => select_players(stdout)?,
+    };
+    let walls = match cli.walls {
+        Some(walls) => walls,
+        None if cli.board_given() => WallMode::Solid,
+        None => {
+            prompted = true;
+            select_wall_mode(stdout)?
+        }
+    };
+    // Cancelling the seed goes back to the difficulty, or asks for the seed
+    // again when the difficulty came from the command line
+    let (speed, profile, seed) = loop {
+        let (speed, profile) = match cli.initial_speed() {
+            Some(speed) => (speed, cli.profile.clone()),
+            None => {
+                prompted = true;
+                select_difficulty(stdout)?
+            }
+        };
+        let seed = match cli.seed {
+            Some(seed) => seed,
+            None => {
+                prompted = true;
+                match select_seed(stdout)? {
+                    Some(seed) => seed,
+                    None => continue,
+                }
+            }
+        };
+        break (speed, profile, seed);
+    };
+
+    if prompted {
+        // Wait a moment before clearing the screen
+        std::thread::sleep(Duration::from_millis(1000));
+        execute!(stdout, terminal::Clear(ClearType::All))?;
+    }
+
+    let settings = Settings {
+        width,
+        height,
+        speed,
+        seed,
+        walls,
+        level,
+        players,
+        profile,
+        food_mix: cli.food_mix(),
+    };
+    Ok((settings, campaign))
+}
+
+/// Show the menu to select the boundary size, a level or, if `allow_campaign`,
+/// the campaign.
+fn select_board_size(stdout: &mut std::io::Stdout, allow_campaign: bool) -> crossterm::Result<Board> {
+    execute!(
+        stdout,
+        cursor::MoveTo(0, 0),
+        SetForegroundColor(Color::White),
+        Print("Select Boundary Size:\n"),
+        Print("1. Small (20x10)\n2. Medium (40x20)\n3. Large (60x30)\n4. Custom...\n5. Level...\n")
+    )?;
+    let choose = if allow_campaign {
+        execute!(stdout, Print("6. Campaign\n"))?;
+        "Press 1, 2, 3, 4, 5, or 6 to choose: "
+    } else {
+        "Press 1, 2, 3, 4, or 5 to choose: "
+    };
+    execute!(stdout, Print(choose))?;
+    stdout.flush()?;
+
+    let width;
+    let height;
+
+    // Get boundary size selection
+    loop {
+        if event::poll(Duration::from_millis(100))? {
+            if let Event::Key(key_event) = event::read()? {
+                if tty::is_interrupt(&key_event) {
+                    tty::exit_interrupted();
+                }
+                match key_event.code {
+                    KeyCode::Char('1') => {
+                        width = 20;
+                        height = 10;
+                        execute!(
+                            stdout,
+                            cursor::MoveToNextLine(1),
+                            Print("You selected Small (20x10)\n")
+                        )?;
+                        break;
+                    }
+                    KeyCode::Char('2') => {
+                        width = 40;
+                        height = 20;
+                        execute!(
+                            stdout,
+                            cursor::MoveToNextLine(1),
+                            Print("You selected Medium (40x20)\n")
+                        )?;
+                        break;
+                    }
+                    KeyCode::Char('3') => {
+                        width = 60;
+                        height = 30;
+                        execute!(
+                            stdout,
+                            cursor::MoveToNextLine(1),
+                            Print("You selected Large (60x30)\n")
+                        )?;
+                        break;
+                    }
+                    KeyCode::Char('4') => {
+                        let Some(size) = select_custom_size(stdout)? else {
+                            // Back to this menu
+                            execute!(stdout, cursor::MoveToNextLine(2), Print(choose))?;
+                            continue;
+                        };
+                        (width, height) = size;
+                        execute!(
+                            stdout,
+                            cursor::MoveToNextLine(1),
+                            Print(format!("You selected Custom ({}x{})\n", width, height))
+                        )?;
+                        break;
+                    }
+                    KeyCode::Char('5') => {
+                        return Ok(Board::Level(select_level(stdout)?));
+                    }
+                    KeyCode::Char('6') if allow_campaign => {
+                        execute!(
+                            stdout,
+                            cursor::MoveToNextLine(1),
+                            Print("You selected Campaign\n")
+                        )?;
+                        return Ok(Board::Campaign);
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    Ok(Board::Size(width, height))
+}
+
+/// Prompt for a board width and height, asking again until both are allowed.
+/// Returns `None` if the player cancels with Esc.
+///
+/// The largest board the terminal can show is suggested, but a larger one can
+/// still be chosen; the game then waits for the terminal to grow.
+fn select_custom_size(stdout: &mut std::io::Stdout) -> crossterm::Result<Option<(i32, i32)>> {
+    let (columns, rows) = terminal::size()?;
+    let (spare_columns, spare_rows) = renderer::required_size(0, 0);
+    let fits = (
+        (columns.saturating_sub(spare_columns) as i32).clamp(MIN_BOARD_SIZE, MAX_BOARD_SIZE),
+        (rows.saturating_sub(spare_rows) as i32).clamp(MIN_BOARD_SIZE, MAX_BOARD_SIZE),
+    );
+    execute!(
+        stdout,
+        cursor::MoveToNextLine(2),
+        Print(format!(
+            "Width and height go from {} to {}, walls included; this terminal fits up to {}x{}.",
+            MIN_BOARD_SIZE, MAX_BOARD_SIZE, fits.0, fits.1
+        )),
+        cursor::MoveToNextLine(1),
+        Print("Press Esc to go back.")
+    )?;
+
+    loop {
+        let Some(width) = prompt_number(stdout, "Width")? else {
+            return Ok(None);
+        };
+        let Some(height) = prompt_number(stdout, "Height")? else {
+            return Ok(None);
+        };
+        match game::check_board_size(width, height) {
+            Ok(()) => return Ok(Some((width, height))),
+            Err(err) => execute!(stdout, cursor::MoveToNextLine(1), Print(format!("Sorry, {}.", err)))?,
+        }
+    }
+}
+
+/// Prompt for a whole number, labelled `label`, on a new line. Anything
+/// unreadable counts as 0; `None` means the player cancelled.
+fn prompt_number(stdout: &mut std::io::Stdout, label: &str) -> crossterm::Result<Option<i32>> {
+    execute!(stdout, cursor::MoveToNextLine(1), Print(format!("{}: ", label)))?;
+    stdout.flush()?;
+    let input = read_input(stdout, 3, |c| c.is_ascii_digit())?;
+    Ok(input.map(|input| input.parse().unwrap_or(0)))
+}
+
+/// Start a new campaign, or resume saved progress. Asks first unless `resume`
+/// is set.
+fn select_campaign(stdout: &mut std::io::Stdout, resume: bool) -> crossterm::Result<Campaign> {
+    let saved = Campaign::default_path().and_then(|path| Campaign::load(&path));
+    let Some(saved) = saved else {
+        return Ok(Campaign::new());
+    };
+    if resume {
+        return Ok(saved);
+    }
+
+    execute!(
+        stdout,
+        cursor::MoveToNextLine(2),
+        Print(format!(
+            "Resume campaign at level {} (Lives: {}, Score: {})? Press y or n: ",
+            saved.level + 1,
+            saved.lives,
+            saved.total_score
+        ))
+    )?;
+    stdout.flush()?;
+
+    loop {
+        if event::poll(Duration::from_millis(100))? {
+            if let Event::Key(key_event) = event::read()? {
+                if tty::is_interrupt(&key_event) {
+                    tty::exit_interrupted();
+                }
+                match key_event.code {
+                    KeyCode::Char('y') => return Ok(saved),
+                    KeyCode::Char('n') => return Ok(Campaign::new()),
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+/// Show the menu to select one of the bundled levels.
+fn select_level(stdout: &mut std::io::Stdout) -> crossterm::Result<Level> {
+    let levels: Vec<Level> = Level::bundled_names()
+        .map(|name| Level::bundled(name).expect("bundled levels are valid"))
+        .collect();
+
+    execute!(stdout, cursor::MoveToNextLine(2), Print("Select Level:\n"))?;
+    for (i, level) in levels.iter().enumerate() {
+        execute!(
+            stdout,
+            Print(format!("{}. {} ({}x{})\n", i + 1, level.name, level.width, level.height))
+        )?;
+    }
+    execute!(stdout, Print(format!("Press 1-{} to choose: ", levels.len())))?;
+    stdout.flush()?;
+
+    loop {
+        if event::poll(Duration::from_millis(100))? {
+            if let Event::Key(key_event) = event::read()? {
+                if tty::is_interrupt(&key_event) {
+                    tty::exit_interrupted();
+                }
+                let choice = match key_event.code {
+                    KeyCode::Char(c) => c.to_digit(10).map(|d| d as usize),
+                    _ => None,
+                };
+                if let Some(level) = choice.and_then(|d| d.checked_sub(1)).and_then(|i| levels.get(i)) {
+                    execute!(
+                        stdout,
+                        cursor::MoveToNextLine(1),
+                        Print(format!("You selected {}\n", level.name))
+                    )?;
+                    return Ok(level.clone());
+                }
+            }
+        }
+    }
+}
+
+/// Show the menu to select whether the walls are solid or wrap around.
+fn select_wall_mode(stdout: &mut std::io::Stdout) -> crossterm::Result<WallMode> {
+    execute!(
+        stdout,
+        cursor::MoveToNextLine(2),
+        Print("Select Walls:\n"),
+        Print("1. Solid (hitting a wall ends the game)\n2. Wrap-around (come back in on the other side)\n"),
+        Print("Press 1 or 2 to choose: ")
+    )?;
+    stdout.flush()?;
+
+    loop {
+        if event::poll(Duration::from_millis(100))? {
+            if let Event::Key(key_event) = event::read()? {
+                if tty::is_interrupt(&key_event) {
+                    tty::exit_interrupted();
+                }
+                let walls = match key_event.code {
+                    KeyCode::Char('1') => WallMode::Solid,
+                    KeyCode::Char('2') => WallMode::Wrap,
+                    _ => continue,
+                };
+                execute!(
+                    stdout,
+                    cursor::MoveToNextLine(1),
+                    Print(format!("You selected {} walls\n", walls.name()))
+                )?;
+                return Ok(walls);
+            }
+        }
+    }
+}
+
+/// Show the menu to select one or two players.
+fn select_players(stdout: &mut std::io::Stdout) -> crossterm::Result<usize> {
+    execute!(
+        stdout,
+        cursor::MoveToNextLine(2),
+        Print("Select Players:\n"),
+        Print("1. One player\n2. Two players (arrows vs WASD)\n"),
+        Print("Press 1 or 2 to choose: ")
+    )?;
+    stdout.flush()?;
+
+    loop {
+        if event::poll(Duration::from_millis(100))? {
+            if let Event::Key(key_event) = event::read()? {
+                if tty::is_interrupt(&key_event) {
+                    tty::exit_interrupted();
+                }
+                let players = match key_event.code {
+                    KeyCode::Char('1') => 1,
+                    KeyCode::Char('2') => 2,
+                    _ => continue,
+                };
+                let text = if players == 1 { "one player" } else { "two players" };
+                execute!(
+                    stdout,
+                    cursor::MoveToNextLine(1),
+                    Print(format!("You selected {}\n", text))
+                )?;
+                return Ok(players);
+            }
+        }
+    }
+}
+
+/// Show the menu to select the difficulty: a fixed speed, or a profile that
+/// speeds up as the score grows. Returns the starting speed and the profile.
+fn select_difficulty(stdout: &mut std::io::Stdout) -> crossterm::Result<(u64, Option<Profile>)> {
+    let difficulties = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+    let profiles = Profile::built_in();
+
+    // Prompt for difficulty selection
+    execute!(stdout, cursor::MoveToNextLine(2), Print("Select Difficulty:\n"))?;
+    for (i, difficulty) in difficulties.iter().enumerate() {
+        execute!(
+            stdout,
+            Print(format!("{}. {} ({}ms per tick)\n", i + 1, difficulty.name(), difficulty.speed()))
+        )?;
+    }
+    for (i, profile) in profiles.iter().enumerate() {
+        execute!(
+            stdout,
+            Print(format!("{}. {} ({})\n", difficulties.len() + i + 1, profile, profile.description()))
+        )?;
+    }
+    let count = difficulties.len() + profiles.len();
+    execute!(stdout, Print(format!("Press 1-{} to choose: ", count)))?;
+    stdout.flush()?;
+
+    // Get difficulty selection
+    loop {
+        if event::poll(Duration::from_millis(100))? {
+            if let Event::Key(key_event) = event::read()? {
+                if tty::is_interrupt(&key_event) {
+                    tty::exit_interrupted();
+                }
+                let choice = match key_event.code {
+                    KeyCode::Char(c) => c.to_digit(10).map(|d| d as usize).filter(|&d| (1..=count).contains(&d)),
+                    _ => None,
+                };
+                let Some(choice) = choice else {
+                    continue;
+                };
+                let (speed, profile, text) = match difficulties.get(choice - 1) {
+                    Some(difficulty) => (
+                        difficulty.speed(),
+                        None,
+                        format!("{} ({}ms per tick)", difficulty.name(), difficulty.speed()),
+                    ),
+                    None => {
+                        let profile = profiles[choice - 1 - difficulties.len()].clone();
+                        let text = format!("{} ({})", profile, profile.description());
+                        (profile.start, Some(profile), text)
+                    }
+                };
+                execute!(
+                    stdout,
+                    cursor::MoveToNextLine(1),
+                    Print(format!("You selected {}\n", text))
+                )?;
+                return Ok((speed, profile));
+            }
+        }
+    }
+}
+
+/// Prompt for a seed, picking a random one if the player just presses Enter.
+/// Returns `None` if the player cancels with Esc.
+fn select_seed(stdout: &mut std::io::Stdout) -> crossterm::Result<Option<u64>> {
+    execute!(
+        stdout,
+        cursor::MoveToNextLine(2),
+        Print("Enter a seed, or press Enter for a random one (Esc to go back): ")
+    )?;
+    stdout.flush()?;
+
+    let Some(input) = read_input(stdout, 20, |c| c.is_ascii_digit())? else {
+        return Ok(None);
+    };
+    let seed = input.parse().unwrap_or_else(|_| rand::random());
+    execute!(
+        stdout,
+        cursor::MoveToNextLine(1),
+        Print(format!("Using seed {}\n", seed))
+    )?;
+    Ok(Some(seed))
+}
+
+/// Make sure the terminal can show a `width` x `height` board, waiting for the
+/// player to enlarge it if not. If `allow_shrink`, the player can instead
+/// press `s` to play on a board that fits.
+///
+/// Returns the board size to play on, or `None` if the player quit with one of
+/// `keys`' quit keys or Ctrl+C.
+pub fn make_room(
+    stdout: &mut std::io::Stdout,
+    keys: &KeyBindings,
+    width: i32,
+    height: i32,
+    allow_shrink: bool,
+) -> crossterm::Result<Option<(i32, i32)>> {
+    make_room_polling(stdout, keys, width, height, allow_shrink, || true)
+}
+
+/// Like [`make_room`], but calls `poll` every so often while waiting, e.g. to
+/// keep a network game alive. If `poll` returns `false`, gives up waiting and
+/// returns `None`.
+pub fn make_room_polling(
+    stdout: &mut std::io::Stdout,
+    keys: &KeyBindings,
+    width: i32,
+    height: i32,
+    allow_shrink: bool,
+    mut poll: impl FnMut() -> bool,
+) -> crossterm::Result<Option<(i32, i32)>> {
+    let (needed_columns, needed_rows) = renderer::required_size(width, height);
+    let mut shown = None;
+    loop {
+        if !poll() {
+            if shown.is_some() {
+                execute!(stdout, terminal::Clear(ClearType::All))?;
+            }
+            return Ok(None);
+        }
+        let (columns, rows) = terminal::size()?;
+        if columns >= needed_columns && rows >= needed_rows {
+            if shown.is_some() {
+                execute!(stdout, terminal::Clear(ClearType::All))?;
+            }
+            return Ok(Some((width, height)));
+        }
+
+        // The largest board that fits, if it isn't below the smallest allowed
+        let spare_rows = needed_rows - height as u16;
+        let shrunk = (width.min(columns as i32), height.min(rows.saturating_sub(spare_rows) as i32));
+        let shrunk = Some(shrunk).filter(|&(w, h)| allow_shrink && w >= MIN_BOARD_SIZE && h >= MIN_BOARD_SIZE);
+
+        if shown != Some((columns, rows)) {
+            shown = Some((columns, rows));
+            execute!(
+                stdout,
+                terminal::Clear(ClearType::All),
+                cursor::MoveTo(0, 0),
+                SetForegroundColor(Color::White),
+                Print(format!(
+                    "The terminal is {}x{}, but a {}x{} board needs {}x{}.\r\n",
+                    columns, rows, width, height, needed_columns, needed_rows
+                )),
+                Print("Enlarge the terminal to continue,\r\n")
+            )?;
+            if let Some((w, h)) = shrunk {
+                execute!(stdout, Print(format!("press s to play on a {}x{} board instead,\r\n", w, h)))?;
+            }
+            execute!(stdout, Print(format!("or press {} to quit.", keys.describe(Action::Quit))))?;
+        }
+
+        // A resize is picked up at the top of the loop
+        if event::poll(Duration::from_millis(100))? {
+            if let Event::Key(key_event) = event::read()? {
+                let choice = match key_event.code {
+                    _ if tty::is_interrupt(&key_event) => None,
+                    code if keys.action(code) == Some(Action::Quit) => None,
+                    KeyCode::Char('s') if shrunk.is_some() => shrunk,
+                    _ => continue,
+                };
+                execute!(stdout, terminal::Clear(ClearType::All))?;
+                return Ok(choice);
+            }
+        }
+    }
+}
+
+/// Ask for the player's name for the high-score table, on screen row `row`.
+pub fn prompt_player_name(stdout: &mut std::io::Stdout, row: u16) -> crossterm::Result<String> {
+    execute!(
+        stdout,
+        cursor::MoveTo(0, row),
+        SetForegroundColor(Color::Yellow),
+        Print("New high score! Enter your name: "),
+        SetForegroundColor(Color::White)
+    )?;
+    stdout.flush()?;
+
+    // Esc still records the score, without a name
+    let name = read_input(stdout, MAX_NAME_LEN, |c| c.is_alphanumeric() || c == ' ' || c == '-' || c == '_')?;
+    let name = name.as_deref().unwrap_or_default().trim();
+    Ok(if name.is_empty() { "Anonymous".to_string() } else { name.to_string() })
+}
+
+/// Read a line of up to `max_len` characters accepted by `accept`, echoing
+/// them as they are typed. Backspace deletes and Enter finishes; Esc cancels,
+/// returning `None`, and Ctrl+C quits like in every other menu.
+pub fn read_input(
+    stdout: &mut std::io::Stdout,
+    max_len: usize,
+    accept: impl Fn(char) -> bool,
+) -> crossterm::Result<Option<String>> {
+    let mut input = String::new();
+    loop {
+        if event::poll(Duration::from_millis(100))? {
+            if let Event::Key(key_event) = event::read()? {
+                match key_event.code {
+                    _ if tty::is_interrupt(&key_event) => tty::exit_interrupted(),
+                    KeyCode::Esc => return Ok(None),
+                    KeyCode::Enter => break,
+                    KeyCode::Backspace if !input.is_empty() => {
+                        input.pop();
+                        execute!(stdout, cursor::MoveLeft(1), Print(" "), cursor::MoveLeft(1))?;
+                    }
+                    KeyCode::Char(c) if accept(c) && input.chars().count() < max_len => {
+                        input.push(c);
+                        execute!(stdout, Print(c))?;
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+    Ok(Some(input))
+}
@@ -0,0 +1,519 @@
+//! Two-player games over TCP.
+//!
+//! The host runs the only authoritative game; the player who joins controls
+//! the second snake. Games are deterministic, so the host never sends the
+//! board: after the settings it sends, for every step, the speed and the
+//! direction each snake moved in, and the client steps its own copy of the
+//! game with them. The client only sends its turns, which the host applies on
+//! its next step, so latency delays a turn but can never make the two copies
+//! disagree.
+//!
+//! The protocol is plain text, one message per line:
+//!
+//! ```text
+//! hello 5                 client: protocol version
+//! welcome 2 5             host: the client's player number, then that many
+//! rusty_snake replay 4      lines of settings in the replay format
+//! board 40 20
+//! speed 200
+//! seed 42
+//! players 2
+//! tick 1 200 R L          host: a step, with the speed and each snake's direction
+//! turn U                  client: a turn for its snake
+//! ack 1                   client: a tick arrived, for measuring latency
+//! paused / resumed        host
+//! ping                    either side, when it has had nothing to say for a while
+//! quit                    either side, when leaving
+//! refused <reason>        host, instead of `welcome`
+//! ```
+//!
+//! Either side counts the other as dropped once it has heard nothing for
+//! [`TIMEOUT`].
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{IpAddr, Shutdown, SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::controller::{KeyboardController, SnakeController, View};
+use crate::game::{Direction, GameEvent, GameState, Settings};
+use crate::replay::{self, Replay};
+
+/// The protocol version spoken by this build. Both sides step the game, so
+/// this changes whenever the rules do, e.g. in version 2 how food is placed,
+/// in version 3 where the first food goes, in version 4 speed profiles and in
+/// version 5 the kinds of food.
+pub const VERSION: u32 = 5;
+
+/// The port used when none is given.
+pub const DEFAULT_PORT: u16 = 7878;
+
+/// How long each side stays quiet before sending a `ping`.
+pub const HEARTBEAT: Duration = Duration::from_secs(1);
+
+/// How long without hearing from the other side before it counts as dropped.
+pub const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The player (0-based) who joins the host.
+const CLIENT_PLAYER: usize = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    Hello(u32),
+    /// The joining player's number (0-based) and the game settings.
+    Welcome { player: usize, settings: Settings },
+    Refused(String),
+    /// One step: the speed it ran at and each snake's direction.
+    Tick {
+        tick: u64,
+        speed: u64,
+        directions: Vec<Direction>,
+    },
+    Paused,
+    Resumed,
+    Turn(Direction),
+    Ack(u64),
+    Ping,
+    Quit,
+}
+
+#[derive(Debug)]
+pub enum NetError {
+    Io(io::Error),
+    /// The other side quit.
+    Left,
+    /// The connection closed without a `quit`.
+    Disconnected,
+    /// Nothing was heard from the other side for [`TIMEOUT`].
+    TimedOut,
+    /// The host turned the client away.
+    Refused(String),
+    /// The other side sent something unexpected.
+    Protocol(String),
+}
+
+impl fmt::Display for NetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetError::Io(err) => write!(f, "{}", err),
+            NetError::Left => write!(f, "the other player left the game"),
+            NetError::Disconnected => write!(f, "the connection was closed"),
+            NetError::TimedOut => write!(f, "the other player stopped responding"),
+            NetError::Refused(reason) => write!(f, "the host refused to start: {}", reason),
+            NetError::Protocol(message) => write!(f, "protocol error: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for NetError {}
+
+impl From<io::Error> for NetError {
+    fn from(err: io::Error) -> Self {
+        NetError::Io(err)
+    }
+}
+
+impl Message {
+    /// Reads the next message, or `None` at the end of the stream.
+    pub fn read(reader: &mut impl BufRead) -> Result<Option<Message>, NetError> {
+        let Some(line) = read_line(reader)? else {
+            return Ok(None);
+        };
+        let err = |message: &str| NetError::Protocol(format!("{} in `{}`", message, line));
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let message = match fields.as_slice() {
+            ["hello", version] => Message::Hello(parse(version, &line)?),
+            ["welcome", player, count] => {
+                let player: usize = parse(player, &line)?;
+                let count: usize = parse(count, &line)?;
+                let mut text = String::new();
+                for _ in 0..count {
+                    let next = read_line(reader)?.ok_or(NetError::Disconnected)?;
+                    text.push_str(&next);
+                    text.push('\n');
+                }
+                let replay: Replay = text.parse().map_err(|e| err(&format!("invalid settings ({})", e)))?;
+                if player == 0 {
+                    return Err(err("players count from 1"));
+                }
+                Message::Welcome {
+                    player: player - 1,
+                    settings: replay.settings,
+                }
+            }
+            ["refused", ..] => Message::Refused(line["refused".len()..].trim().to_string()),
+            ["tick", tick, speed, directions @ ..] => Message::Tick {
+                tick: parse(tick, &line)?,
+                speed: parse(speed, &line)?,
+                directions: directions
+                    .iter()
+                    .map(|d| replay::parse_direction(d).ok_or_else(|| err("unknown direction")))
+                    .collect::<Result<_, _>>()?,
+            },
+            ["paused"] => Message::Paused,
+            ["resumed"] => Message::Resumed,
+            ["turn", d] => Message::Turn(replay::parse_direction(d).ok_or_else(|| err("unknown direction"))?),
+            ["ack", tick] => Message::Ack(parse(tick, &line)?),
+            ["ping"] => Message::Ping,
+            ["quit"] => Message::Quit,
+            _ => return Err(err("unknown message")),
+        };
+        Ok(Some(message))
+    }
+
+    /// Writes the message, followed by a newline.
+    pub fn write(&self, writer: &mut impl Write) -> io::Result<()> {
+        let text = match self {
+            Message::Hello(version) => format!("hello {}\n", version),
+            Message::Welcome { player, settings } => {
+                let replay = Replay {
+                    settings: settings.clone(),
+                    events: Vec::new(),
+                    end: None,
+                }
+                .to_string();
+                format!("welcome {} {}\n{}", player + 1, replay.lines().count(), replay)
+            }
+            Message::Refused(reason) => format!("refused {}\n", reason),
+            Message::Tick { tick, speed, directions } => {
+                let directions: Vec<String> = directions
+                    .iter()
+                    .map(|&d| replay::direction_char(d).to_string())
+                    .collect();
+                format!("tick {} {} {}\n", tick, speed, directions.join(" "))
+            }
+            Message::Paused => "paused\n".to_string(),
+            Message::Resumed => "resumed\n".to_string(),
+            Message::Turn(direction) => format!("turn {}\n", replay::direction_char(*direction)),
+            Message::Ack(tick) => format!("ack {}\n", tick),
+            Message::Ping => "ping\n".to_string(),
+            Message::Quit => "quit\n".to_string(),
+        };
+        writer.write_all(text.as_bytes())?;
+        writer.flush()
+    }
+}
+
+fn read_line(reader: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(line.trim_end().to_string()))
+}
+
+fn parse<T: std::str::FromStr>(field: &str, line: &str) -> Result<T, NetError> {
+    field
+        .parse()
+        .map_err(|_| NetError::Protocol(format!("invalid number `{}` in `{}`", field, line)))
+}
+
+/// One side of a connection. Incoming messages are read on a background
+/// thread, so polling never blocks the game loop.
+#[derive(Debug)]
+pub struct Connection {
+    stream: TcpStream,
+    incoming: Receiver<Result<Message, NetError>>,
+    last_heard: Instant,
+    last_sent: Instant,
+}
+
+impl Connection {
+    pub fn new(stream: TcpStream) -> io::Result<Self> {
+        // Ticks are tiny and time-critical, so don't let them sit in a buffer
+        stream.set_nodelay(true)?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let (sender, incoming) = mpsc::channel();
+        thread::spawn(move || loop {
+            let result = Message::read(&mut reader);
+            let done = !matches!(result, Ok(Some(_)));
+            // A closed stream ends the thread, which the receiver sees as a disconnect
+            if let Some(message) = result.transpose() {
+                if sender.send(message).is_err() {
+                    break;
+                }
+            }
+            if done {
+                break;
+            }
+        });
+        Ok(Connection {
+            stream,
+            incoming,
+            last_heard: Instant::now(),
+            last_sent: Instant::now(),
+        })
+    }
+
+    pub fn send(&mut self, message: &Message) -> Result<(), NetError> {
+        message.write(&mut self.stream)?;
+        self.last_sent = Instant::now();
+        Ok(())
+    }
+
+    /// The next message, if one has arrived. `quit`, a closed connection and
+    /// silence for longer than [`TIMEOUT`] are errors.
+    pub fn poll(&mut self) -> Result<Option<Message>, NetError> {
+        match self.incoming.try_recv() {
+            Ok(Ok(Message::Quit)) => Err(NetError::Left),
+            Ok(Ok(message)) => {
+                self.last_heard = Instant::now();
+                Ok(Some(message))
+            }
+            Ok(Err(err)) => Err(err),
+            Err(TryRecvError::Disconnected) => Err(NetError::Disconnected),
+            Err(TryRecvError::Empty) if self.last_heard.elapsed() > TIMEOUT => Err(NetError::TimedOut),
+            Err(TryRecvError::Empty) => Ok(None),
+        }
+    }
+
+    /// Waits up to [`TIMEOUT`] for the next message.
+    pub fn recv(&mut self) -> Result<Message, NetError> {
+        loop {
+            if let Some(message) = self.poll()? {
+                return Ok(message);
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    /// Sends a `ping` if nothing has been sent for [`HEARTBEAT`].
+    pub fn keep_alive(&mut self) -> Result<(), NetError> {
+        if self.last_sent.elapsed() >= HEARTBEAT {
+            self.send(&Message::Ping)?;
+        }
+        Ok(())
+    }
+
+    /// Tells the other side we are leaving. Errors are ignored, as the
+    /// connection may already be gone.
+    pub fn quit(mut self) {
+        let _ = self.send(&Message::Quit);
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        // The reader thread holds a clone of the stream, so close it explicitly
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
+
+/// The host's side of a game: sends the client every step, and controls the
+/// client's snake with the turns it sends back.
+#[derive(Debug)]
+pub struct Host {
+    connection: Connection,
+    /// The settings to welcome the client with, until its `hello` arrives.
+    welcome: Option<Settings>,
+    /// The client's turns since the last step.
+    turns: KeyboardController,
+    /// Ticks sent but not yet acknowledged, with when they were sent.
+    unacked: VecDeque<(u64, Instant)>,
+    /// The last measured round trip to the client.
+    pub latency: Option<Duration>,
+}
+
+impl Host {
+    /// Starts greeting a client that has just connected. This never waits:
+    /// [`Host::poll`] sends the settings once the client's `hello` arrives,
+    /// and fails if it doesn't within [`TIMEOUT`]. The game can start once
+    /// [`Host::is_ready`].
+    pub fn accept(stream: TcpStream, settings: &Settings) -> Result<Host, NetError> {
+        stream.set_nonblocking(false)?;
+        Ok(Host {
+            connection: Connection::new(stream)?,
+            welcome: Some(settings.clone()),
+            turns: KeyboardController::new(),
+            unacked: VecDeque::new(),
+            latency: None,
+        })
+    }
+
+    /// Whether the client has been welcomed and the game can start.
+    pub fn is_ready(&self) -> bool {
+        self.welcome.is_none()
+    }
+
+    /// The player (0-based) the client controls.
+    pub fn player(&self) -> usize {
+        CLIENT_PLAYER
+    }
+
+    /// Handles everything the client has sent, welcoming it first. Its turns
+    /// are kept for the next step; see the [`SnakeController`] impl.
+    pub fn poll(&mut self) -> Result<(), NetError> {
+        while let Some(message) = self.connection.poll()? {
+            if !self.is_ready() {
+                self.greet(message)?;
+                continue;
+            }
+            match message {
+                Message::Turn(direction) => self.turns.press(direction),
+                Message::Ack(tick) => {
+                    while let Some(&(sent_tick, sent_at)) = self.unacked.front() {
+                        if sent_tick > tick {
+                            break;
+                        }
+                        if sent_tick == tick {
+                            self.latency = Some(sent_at.elapsed());
+                        }
+                        self.unacked.pop_front();
+                    }
+                }
+                Message::Ping => {}
+                other => return Err(NetError::Protocol(format!("unexpected {:?} from the client", other))),
+            }
+        }
+        // A ping before the welcome would confuse the client
+        if self.is_ready() {
+            self.connection.keep_alive()?;
+        }
+        Ok(())
+    }
+
+    /// Answers the client's first message, which must be a `hello` in our
+    /// version.
+    fn greet(&mut self, message: Message) -> Result<(), NetError> {
+        match message {
+            Message::Hello(VERSION) => {}
+            Message::Hello(version) => {
+                let reason = format!("version {} is not supported, the host speaks {}", version, VERSION);
+                self.connection.send(&Message::Refused(reason.clone()))?;
+                return Err(NetError::Protocol(reason));
+            }
+            other => return Err(NetError::Protocol(format!("expected hello, got {:?}", other))),
+        }
+        match self.welcome.take() {
+            Some(settings) => self.connection.send(&Message::Welcome {
+                player: CLIENT_PLAYER,
+                settings,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Sends the step `game` just took.
+    pub fn send_tick(&mut self, game: &GameState) -> Result<(), NetError> {
+        self.unacked.push_back((game.tick, Instant::now()));
+        self.connection.send(&Message::Tick {
+            tick: game.tick,
+            speed: game.speed,
+            directions: game.snakes.iter().map(|snake| snake.direction).collect(),
+        })
+    }
+
+    /// Tells the client the game was paused or resumed.
+    pub fn send_paused(&mut self, paused: bool) -> Result<(), NetError> {
+        self.connection
+            .send(if paused { &Message::Paused } else { &Message::Resumed })
+    }
+
+    pub fn quit(self) {
+        self.connection.quit();
+    }
+}
+
+impl SnakeController for Host {
+    fn next_direction(&mut self, view: &View) -> Direction {
+        self.turns.next_direction(view)
+    }
+}
+
+/// Something the host told the client, after it has been applied to the game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Update {
+    Stepped(Vec<GameEvent>),
+    Paused(bool),
+}
+
+/// Looks up the host to join: `host:port`, `[ipv6]:port`, or an address or
+/// host name on its own for the [`DEFAULT_PORT`].
+pub fn resolve(addr: &str) -> io::Result<SocketAddr> {
+    if let Ok(socket_addr) = addr.parse::<SocketAddr>() {
+        return Ok(socket_addr);
+    }
+    // Checked before looking for a port, as a bare IPv6 address has colons too
+    if let Ok(ip) = addr.parse::<IpAddr>() {
+        return Ok(SocketAddr::new(ip, DEFAULT_PORT));
+    }
+    let addr = if addr.contains(':') {
+        addr.to_string()
+    } else {
+        format!("{}:{}", addr, DEFAULT_PORT)
+    };
+    addr.to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("no address found for {}", addr)))
+}
+
+/// The joining player's side of a game: a copy of the host's game, stepped
+/// as the host's ticks arrive.
+#[derive(Debug)]
+pub struct Client {
+    connection: Connection,
+    /// This client's player (0-based).
+    pub player: usize,
+    pub settings: Settings,
+}
+
+impl Client {
+    /// Connects to a host at `addr` (see [`resolve`]) and waits for the game
+    /// settings.
+    pub fn join(addr: &str) -> Result<Client, NetError> {
+        let stream = TcpStream::connect_timeout(&resolve(addr)?, TIMEOUT)?;
+        let mut connection = Connection::new(stream)?;
+        connection.send(&Message::Hello(VERSION))?;
+        match connection.recv()? {
+            Message::Welcome { player, settings } => Ok(Client {
+                connection,
+                player,
+                settings,
+            }),
+            Message::Refused(reason) => Err(NetError::Refused(reason)),
+            other => Err(NetError::Protocol(format!("expected welcome, got {:?}", other))),
+        }
+    }
+
+    /// Applies the next message from the host to `game`, if one has arrived.
+    pub fn poll(&mut self, game: &mut GameState) -> Result<Option<Update>, NetError> {
+        self.connection.keep_alive()?;
+        let update = match self.connection.poll()? {
+            Some(Message::Tick { tick, speed, directions }) => {
+                if tick != game.tick + 1 || directions.len() != game.snakes.len() {
+                    return Err(NetError::Protocol(format!("tick {} does not follow {}", tick, game.tick)));
+                }
+                game.speed = speed;
+                let turns: Vec<Option<Direction>> = directions.into_iter().map(Some).collect();
+                let events = game.step(&turns);
+                self.connection.send(&Message::Ack(tick))?;
+                Update::Stepped(events)
+            }
+            Some(Message::Paused) => Update::Paused(true),
+            Some(Message::Resumed) => Update::Paused(false),
+            Some(Message::Ping) | None => return Ok(None),
+            Some(other) => return Err(NetError::Protocol(format!("unexpected {:?} from the host", other))),
+        };
+        Ok(Some(update))
+    }
+
+    /// Sends a `ping` if nothing has been sent for a while, so the host
+    /// doesn't drop this client while it isn't polling, e.g. while the
+    /// terminal is too small for the board. Messages from the host wait for
+    /// the next [`Client::poll`].
+    pub fn keep_alive(&mut self) -> Result<(), NetError> {
+        self.connection.keep_alive()
+    }
+
+    /// Asks the host to turn this client's snake.
+    pub fn turn(&mut self, direction: Direction) -> Result<(), NetError> {
+        self.connection.send(&Message::Turn(direction))
+    }
+
+    pub fn quit(self) {
+        self.connection.quit();
+    }
+}
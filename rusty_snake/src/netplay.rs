@@ -0,0 +1,323 @@
+use crate::menu;
+use crate::renderer::{Layout, Renderer};
+use crate::tty;
+use crate::{draw_score, draw_step_events, game_over_message, Prefs};
+use crossterm::{
+    cursor,
+    event::{self, Event},
+    execute, queue,
+    style::{Color, Print, SetForegroundColor},
+    terminal::{self, ClearType},
+};
+use rusty_snake::controller::{self, KeyboardController};
+use rusty_snake::game::{GameState, Settings};
+use rusty_snake::keys::Action;
+use rusty_snake::net::{Client, Host, NetError, Update};
+use rusty_snake::replay::{Recorder, Replay};
+use std::io::{self, Write};
+use std::net::{SocketAddr, TcpListener};
+use std::time::{Duration, Instant};
+
+/// How a network game ended: the final state, the message to show and the
+/// winner (0-based), counting a player who leaves or drops as losing.
+pub type NetResult = (GameState, String, Option<usize>);
+
+/// Waits for a player to join on `listener`, then sends them the settings.
+/// Returns `None` if the host pressed the quit key first.
+pub fn wait_for_client(
+    stdout: &mut std::io::Stdout,
+    listener: &TcpListener,
+    settings: &Settings,
+    prefs: &Prefs,
+) -> crossterm::Result<Option<Host>> {
+    let port = listener.local_addr()?.port();
+    execute!(
+        stdout,
+        terminal::Clear(ClearType::All),
+        cursor::MoveTo(0, 0),
+        SetForegroundColor(Color::White),
+        Print(format!("Hosting on port {}. Waiting for player 2 to join...\r\n", port)),
+        Print(format!("They can run: rusty_snake join <this machine's address>:{}\r\n", port)),
+        Print(format!("Press {} to cancel.", prefs.keys.describe(Action::Quit)))
+    )?;
+    stdout.flush()?;
+
+    // Poll for a connection, and then for its hello, so the host can still cancel
+    listener.set_nonblocking(true)?;
+    let mut joining: Option<(Host, SocketAddr)> = None;
+    loop {
+        while event::poll(Duration::from_millis(0))? {
+            if let Event::Key(key_event) = event::read()? {
+                if tty::is_interrupt(&key_event) || prefs.keys.action(key_event.code) == Some(Action::Quit) {
+                    return Ok(None);
+                }
+            }
+        }
+        let result = match joining.take() {
+            Some((mut host, addr)) => match host.poll() {
+                Ok(()) if host.is_ready() => return Ok(Some(host)),
+                Ok(()) => {
+                    joining = Some((host, addr));
+                    Ok(())
+                }
+                Err(err) => Err((addr, err)),
+            },
+            None => match listener.accept() {
+                Ok((stream, addr)) => match Host::accept(stream, settings) {
+                    Ok(host) => {
+                        joining = Some((host, addr));
+                        Ok(())
+                    }
+                    Err(err) => Err((addr, err)),
+                },
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => Ok(()),
+                Err(err) => return Err(err),
+            },
+        };
+        if let Err((addr, err)) = result {
+            // Keep waiting for someone else
+            execute!(
+                stdout,
+                cursor::MoveTo(0, 4),
+                terminal::Clear(ClearType::CurrentLine),
+                Print(format!("{} could not join: {}", addr, err))
+            )?;
+        }
+        std::thread::sleep(Duration::from_millis(if joining.is_some() { 10 } else { 50 }));
+    }
+}
+
+/// Runs a hosted game: the host plays player 1 on this keyboard and runs the
+/// game, sending every step to the client.
+pub fn host_game(
+    stdout: &mut std::io::Stdout,
+    settings: &Settings,
+    mut host: Host,
+    prefs: &Prefs,
+) -> crossterm::Result<(NetResult, Replay)> {
+    let mut game = GameState::new(settings);
+    let mut recorder = Recorder::new(settings, &game);
+    let mut last_instant = Instant::now();
+    let mut keyboard = KeyboardController::new();
+    let mut renderer = Renderer::new(&game, &prefs.theme);
+
+    execute!(stdout, terminal::Clear(ClearType::All))?;
+    draw_score(stdout, &game)?;
+
+    let (message, winner) = 'game_loop: loop {
+        // 1) Drain the keyboard
+        while event::poll(Duration::from_millis(0))? {
+            let key_event = match event::read()? {
+                Event::Key(key_event) => key_event,
+                Event::Resize(..) => {
+                    // Pause, since the board has moved, and draw it all again
+                    if !game.is_paused() {
+                        game.toggle_pause();
+                        if let Err(err) = host.send_paused(true) {
+                            break 'game_loop dropped(&err, 0);
+                        }
+                    }
+                    // Wait for the terminal to fit the board again, keeping
+                    // the connection alive meanwhile
+                    let mut lost = None;
+                    let keep_alive = || match host.poll() {
+                        Ok(()) => true,
+                        Err(err) => {
+                            lost = Some(err);
+                            false
+                        }
+                    };
+                    let room = menu::make_room_polling(stdout, &prefs.keys, game.width, game.height, false, keep_alive)?;
+                    if let Some(err) = lost {
+                        break 'game_loop dropped(&err, 0);
+                    }
+                    if room.is_none() {
+                        host.quit();
+                        break 'game_loop ("You quit!".to_string(), Some(1));
+                    }
+                    execute!(stdout, terminal::Clear(ClearType::All))?;
+                    draw_score(stdout, &game)?;
+                    renderer.invalidate();
+                    continue;
+                }
+                _ => continue,
+            };
+            let action = prefs.keys.action(key_event.code);
+            let direction = match action {
+                _ if tty::is_interrupt(&key_event) || action == Some(Action::Quit) => {
+                    host.quit();
+                    break 'game_loop ("You quit!".to_string(), Some(1));
+                }
+                Some(Action::Pause) => {
+                    game.toggle_pause();
+                    if let Err(err) = host.send_paused(game.is_paused()) {
+                        break 'game_loop dropped(&err, 0);
+                    }
+                    continue;
+                }
+                Some(Action::Faster) => {
+                    game.faster();
+                    recorder.observe(&game);
+                    draw_score(stdout, &game)?;
+                    continue;
+                }
+                Some(Action::Slower) => {
+                    game.slower();
+                    recorder.observe(&game);
+                    draw_score(stdout, &game)?;
+                    continue;
+                }
+                // Either player's keys steer this player's snake
+                _ => match action.and_then(Action::turn) {
+                    Some((_, direction)) => direction,
+                    None => continue,
+                },
+            };
+            keyboard.press(direction);
+        }
+
+        // 2) Collect the client's turns
+        if let Err(err) = host.poll() {
+            break 'game_loop dropped(&err, 0);
+        }
+
+        // 3) Step and send the step to the client
+        if !game.is_paused() && last_instant.elapsed() >= Duration::from_millis(game.speed) {
+            last_instant = Instant::now();
+
+            let events = controller::step(&mut game, &mut [&mut keyboard, &mut host]);
+            recorder.observe(&game);
+            if let Err(err) = host.send_tick(&game) {
+                break 'game_loop dropped(&err, 0);
+            }
+            draw_step_events(stdout, &game, &events)?;
+            if game.is_over() {
+                break 'game_loop (game_over_message(&game), game.winner());
+            }
+        }
+
+        // 4) Render
+        let latency = match host.latency {
+            Some(latency) => format!("{}ms", latency.as_millis()),
+            None => "-".to_string(),
+        };
+        let paused = if game.is_paused() { " | Paused" } else { "" };
+        let layout = Layout::new(&game)?;
+        queue!(
+            stdout,
+            layout.below(0),
+            terminal::Clear(ClearType::CurrentLine),
+            SetForegroundColor(Color::White),
+            Print(format!("Hosting | Player 2 ping: {}{}", latency, paused))
+        )?;
+        renderer.draw(&game);
+        renderer.flush(stdout, &layout)?;
+        std::thread::sleep(Duration::from_millis(10));
+    };
+
+    let replay = recorder.finish(&game);
+    Ok(((game, message, winner), replay))
+}
+
+/// Runs a joined game: steps a copy of the host's game as its ticks arrive and
+/// sends this player's turns. Player 1's and player 2's keys both steer.
+pub fn join_game(stdout: &mut std::io::Stdout, mut client: Client, prefs: &Prefs) -> crossterm::Result<NetResult> {
+    let mut game = GameState::new(&client.settings);
+    let player = client.player;
+    let host_player = if player == 0 { 1 } else { 0 };
+    let mut paused = false;
+    let mut renderer = Renderer::new(&game, &prefs.theme);
+
+    // The host chose the board, so wait for the terminal to fit it, keeping
+    // the connection alive meanwhile
+    let mut lost = None;
+    let keep_alive = || match client.keep_alive() {
+        Ok(()) => true,
+        Err(err) => {
+            lost = Some(err);
+            false
+        }
+    };
+    let room = menu::make_room_polling(stdout, &prefs.keys, game.width, game.height, false, keep_alive)?;
+    if let Some(err) = lost {
+        let (message, winner) = dropped(&err, player);
+        return Ok((game, message, winner));
+    }
+    if room.is_none() {
+        client.quit();
+        return Ok((game, "You quit!".to_string(), Some(host_player)));
+    }
+
+    execute!(stdout, terminal::Clear(ClearType::All))?;
+    draw_score(stdout, &game)?;
+
+    let (message, winner) = 'game_loop: loop {
+        // 1) Drain the keyboard, sending turns to the host
+        while event::poll(Duration::from_millis(0))? {
+            let key_event = match event::read()? {
+                Event::Key(key_event) => key_event,
+                Event::Resize(..) => {
+                    // Draw it all again where the board now goes
+                    execute!(stdout, terminal::Clear(ClearType::All))?;
+                    draw_score(stdout, &game)?;
+                    renderer.invalidate();
+                    continue;
+                }
+                _ => continue,
+            };
+            let action = prefs.keys.action(key_event.code);
+            let direction = match action {
+                _ if tty::is_interrupt(&key_event) || action == Some(Action::Quit) => {
+                    client.quit();
+                    break 'game_loop ("You quit!".to_string(), Some(host_player));
+                }
+                // Either player's keys steer this player's snake
+                _ => match action.and_then(Action::turn) {
+                    Some((_, direction)) => direction,
+                    None => continue,
+                },
+            };
+            if let Err(err) = client.turn(direction) {
+                break 'game_loop dropped(&err, player);
+            }
+        }
+
+        // 2) Apply everything the host has sent
+        loop {
+            match client.poll(&mut game) {
+                Ok(Some(Update::Stepped(events))) => {
+                    draw_score(stdout, &game)?;
+                    draw_step_events(stdout, &game, &events)?;
+                    if game.is_over() {
+                        break 'game_loop (game_over_message(&game), game.winner());
+                    }
+                }
+                Ok(Some(Update::Paused(now_paused))) => paused = now_paused,
+                Ok(None) => break,
+                Err(err) => break 'game_loop dropped(&err, player),
+            }
+        }
+
+        // 3) Render
+        let paused = if paused { " | Paused by the host" } else { "" };
+        let layout = Layout::new(&game)?;
+        queue!(
+            stdout,
+            layout.below(0),
+            terminal::Clear(ClearType::CurrentLine),
+            SetForegroundColor(Color::White),
+            Print(format!("You are player {}{}", player + 1, paused))
+        )?;
+        renderer.draw(&game);
+        renderer.flush(stdout, &layout)?;
+        std::thread::sleep(Duration::from_millis(10));
+    };
+
+    Ok((game, message, winner))
+}
+
+/// Ends a game because the other side left or the connection failed; the
+/// player still here wins.
+fn dropped(err: &NetError, winner: usize) -> (String, Option<usize>) {
+    (format!("Game over: {}.", err), Some(winner))
+}
@@ -0,0 +1,153 @@
+use crate::menu;
+use crate::renderer::{Layout, Renderer};
+use crate::tty;
+use crate::{draw_score, draw_step_events, draw_victory, game_over_message, wait_for_enter, Prefs};
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode},
+    execute,
+    style::{Color, Print, SetForegroundColor},
+    terminal::{self, ClearType},
+};
+use rusty_snake::game::GameState;
+use rusty_snake::keys::{Action, KeyBindings};
+use rusty_snake::replay::{Player, Replay};
+use std::time::{Duration, Instant};
+
+/// Fast-forward multipliers cycled through with `f`.
+const PLAYBACK_RATES: [u64; 4] = [1, 2, 4, 8];
+
+/// Plays a replay through the normal renderer.
+///
+/// Space pauses, `f` cycles the fast-forward rate, `n` steps one tick while
+/// paused and the quit key stops.
+pub fn play_replay(stdout: &mut std::io::Stdout, replay: &Replay, prefs: &Prefs) -> crossterm::Result<()> {
+    let mut game = replay.new_game();
+    let mut player = Player::new(replay);
+    let mut last_instant = Instant::now();
+    let mut paused = false;
+    let mut rate = 0;
+    let mut renderer = Renderer::new(&game, &prefs.theme);
+
+    // The recorded board can't shrink, so wait for the terminal to fit it
+    if menu::make_room(stdout, &prefs.keys, game.width, game.height, false)?.is_none() {
+        return Ok(());
+    }
+
+    execute!(stdout, terminal::Clear(ClearType::All))?;
+    draw_score(stdout, &game)?;
+    draw_playback_status(stdout, &game, paused, PLAYBACK_RATES[rate], &prefs.keys)?;
+
+    let message = loop {
+        //
+        // 1) Handle playback controls
+        //
+        let mut step_once = false;
+        while event::poll(Duration::from_millis(0))? {
+            match event::read()? {
+                Event::Key(key_event) => {
+                    match key_event.code {
+                        _ if tty::is_interrupt(&key_event) || prefs.keys.action(key_event.code) == Some(Action::Quit) => {
+                            return Ok(())
+                        }
+                        KeyCode::Char(' ') => paused = !paused,
+                        KeyCode::Char('f') => rate = (rate + 1) % PLAYBACK_RATES.len(),
+                        KeyCode::Char('n') if paused => step_once = true,
+                        _ => {}
+                    }
+                    draw_playback_status(stdout, &game, paused, PLAYBACK_RATES[rate], &prefs.keys)?;
+                }
+                Event::Resize(..) => {
+                    // Pause and draw everything again where the board now goes,
+                    // once the terminal fits it again
+                    paused = true;
+                    if menu::make_room(stdout, &prefs.keys, game.width, game.height, false)?.is_none() {
+                        return Ok(());
+                    }
+                    execute!(stdout, terminal::Clear(ClearType::All))?;
+                    draw_score(stdout, &game)?;
+                    draw_playback_status(stdout, &game, paused, PLAYBACK_RATES[rate], &prefs.keys)?;
+                    renderer.invalidate();
+                }
+                _ => {}
+            }
+        }
+
+        //
+        // 2) Step when it's time, or when asked to while paused
+        //
+        let tick = Duration::from_millis(game.speed / PLAYBACK_RATES[rate]);
+        if step_once || (!paused && last_instant.elapsed() >= tick) {
+            last_instant = Instant::now();
+            let speed = game.speed;
+            let events = player.step(&mut game);
+            if game.speed != speed {
+                draw_score(stdout, &game)?;
+            }
+            draw_step_events(stdout, &game, &events)?;
+            if game.is_over() {
+                // Show the final move, such as the last food eaten on a full board
+                renderer.draw(&game);
+                renderer.flush(stdout, &Layout::new(&game)?)?;
+                break game_over_message(&game);
+            }
+            // A game the player quit ends without dying
+            if replay.end.as_ref().is_some_and(|(tick, _)| game.tick >= *tick) {
+                break "The recording ends here.".to_string();
+            }
+        }
+
+        renderer.draw(&game);
+        renderer.flush(stdout, &Layout::new(&game)?)?;
+        std::thread::sleep(Duration::from_millis(10));
+    };
+
+    // Compare the outcome with what was recorded
+    let scores: Vec<i32> = game.snakes.iter().map(|snake| snake.score).collect();
+    let verdict = match &replay.end {
+        Some((tick, end_scores)) if *tick == game.tick && *end_scores == scores => "Replay matches the recording.",
+        Some(_) => "Replay diverged from the recording!",
+        None => "Replay finished.",
+    };
+    if game.is_won() {
+        draw_victory(stdout, &game)?;
+    }
+    let layout = Layout::new(&game)?;
+    execute!(
+        stdout,
+        layout.below(1),
+        terminal::Clear(ClearType::CurrentLine),
+        SetForegroundColor(Color::White),
+        Print(format!("{} {}", message, verdict)),
+        layout.below(2),
+        Print("Press Enter to continue...")
+    )?;
+    wait_for_enter()?;
+
+    execute!(stdout, terminal::Clear(ClearType::All), cursor::MoveTo(0, 0))?;
+    Ok(())
+}
+
+/// Draws the playback state and controls two lines below the board.
+fn draw_playback_status(
+    stdout: &mut std::io::Stdout,
+    game: &GameState,
+    paused: bool,
+    rate: u64,
+    keys: &KeyBindings,
+) -> crossterm::Result<()> {
+    let state = if paused { "Paused" } else { "Playing" };
+    execute!(
+        stdout,
+        Layout::new(game)?.below(1),
+        terminal::Clear(ClearType::CurrentLine),
+        SetForegroundColor(Color::White),
+        Print(format!(
+            "Replay: {} x{} | Space pause | f fast-forward | n step | {} quit",
+            state,
+            rate,
+            keys.describe(Action::Quit)
+        ))
+    )?;
+    Ok(())
+}
@@ -0,0 +1,130 @@
+//! Difficulty profiles: games that speed up by themselves as the score grows.
+//!
+//! A profile sets the starting tick, the fastest tick it will go to and the
+//! curve in between. Every `foods` foods is a level, shown in the status line.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::game::SPEED_STEP;
+
+/// How a profile's tick shortens as the score grows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Curve {
+    /// One `SPEED_STEP` faster at each new level.
+    Steps,
+    /// A little faster with every food, halving the gap to the fastest tick
+    /// at each new level.
+    Smooth,
+}
+
+impl Curve {
+    pub fn name(self) -> &'static str {
+        match self {
+            Curve::Steps => "steps",
+            Curve::Smooth => "smooth",
+        }
+    }
+}
+
+impl FromStr for Curve {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "steps" => Ok(Curve::Steps),
+            "smooth" => Ok(Curve::Smooth),
+            _ => Err(format!("unknown curve `{}` (expected steps or smooth)", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    pub name: String,
+    /// Milliseconds per tick at the start.
+    pub start: u64,
+    /// The fastest tick the curve goes to, in milliseconds.
+    pub min: u64,
+    pub curve: Curve,
+    /// Foods per level.
+    pub foods: u32,
+}
+
+impl Profile {
+    /// The profiles offered in the settings menu and by `--profile`.
+    pub fn built_in() -> Vec<Profile> {
+        let profile = |name: &str, start, min, curve, foods| Profile {
+            name: name.to_string(),
+            start,
+            min,
+            curve,
+            foods,
+        };
+        vec![
+            profile("arcade", 200, 50, Curve::Steps, 5),
+            profile("marathon", 300, 60, Curve::Smooth, 10),
+            profile("sprint", 150, 50, Curve::Steps, 3),
+        ]
+    }
+
+    /// The built-in profile called `name`, ignoring case.
+    pub fn find(name: &str) -> Result<Profile, String> {
+        let built_in = Profile::built_in();
+        let names: Vec<String> = built_in.iter().map(|profile| profile.name.clone()).collect();
+        built_in
+            .into_iter()
+            .find(|profile| profile.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("no profile named `{}` (try {})", name, names.join(", ")))
+    }
+
+    /// The level reached with `score`, counting from 1.
+    pub fn level(&self, score: i32) -> u32 {
+        1 + score.max(0) as u32 / self.foods.max(1)
+    }
+
+    /// Milliseconds per tick once `score` is reached.
+    pub fn tick(&self, score: i32) -> u64 {
+        let min = self.min.min(self.start);
+        match self.curve {
+            Curve::Steps => {
+                let steps = u64::from(self.level(score) - 1);
+                self.start.saturating_sub(steps * SPEED_STEP).max(min)
+            }
+            Curve::Smooth => {
+                // Integer math, so every machine agrees on the tick
+                let foods = u64::from(self.foods.max(1));
+                let levels = u64::from(self.level(score) - 1).min(63);
+                let gap = (self.start - min) >> levels;
+                let into_level = score.max(0) as u64 % foods;
+                min + gap - (gap - gap / 2) * into_level / foods
+            }
+        }
+    }
+
+    /// A one-line summary for the settings menu, e.g.
+    /// `200ms, one step faster every 5 foods, down to 50ms`.
+    pub fn description(&self) -> String {
+        match self.curve {
+            Curve::Steps => format!(
+                "{}ms, one step faster every {} foods, down to {}ms",
+                self.start, self.foods, self.min
+            ),
+            Curve::Smooth => format!(
+                "{}ms, a little faster with every food, down to {}ms",
+                self.start, self.min
+            ),
+        }
+    }
+}
+
+/// The profile's name with its first letter capitalized, e.g. `Arcade`.
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut chars = self.name.chars();
+        match chars.next() {
+            Some(first) => write!(f, "{}{}", first.to_uppercase(), chars.as_str()),
+            None => Ok(()),
+        }
+    }
+}
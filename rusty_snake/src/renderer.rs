@@ -0,0 +1,203 @@
+use crossterm::{
+    cursor, queue,
+    style::{Color, Print, SetForegroundColor},
+    terminal,
+};
+use rusty_snake::game::{GameState, Point, Snake, WallMode};
+use rusty_snake::theme::Theme;
+use std::io::Write;
+
+/// Lines the game screen needs above the board: the score line.
+const LINES_ABOVE: u16 = 1;
+/// Lines the game screen needs below the board, for the banner, status lines
+/// and the game-over prompt.
+const LINES_BELOW: u16 = 3;
+
+/// The terminal size, in columns and rows, needed to show a board of this size.
+pub fn required_size(width: i32, height: i32) -> (u16, u16) {
+    (width as u16, height as u16 + LINES_ABOVE + LINES_BELOW)
+}
+
+/// Where the board sits on screen: centered in the terminal, with the score
+/// line just above it and the status lines just below.
+///
+/// If the terminal is too small the board is pushed against the top-left
+/// corner instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Layout {
+    /// The column of the board's left wall.
+    pub left: u16,
+    /// The row of the board's top wall.
+    pub top: u16,
+    height: u16,
+}
+
+impl Layout {
+    /// The layout for `game`'s board in the terminal as it is now.
+    pub fn new(game: &GameState) -> crossterm::Result<Self> {
+        let (columns, rows) = terminal::size()?;
+        let (needed_columns, needed_rows) = required_size(game.width, game.height);
+        Ok(Layout {
+            left: columns.saturating_sub(needed_columns) / 2,
+            top: rows.saturating_sub(needed_rows) / 2 + LINES_ABOVE,
+            height: game.height as u16,
+        })
+    }
+
+    /// The start of the score line, above the board.
+    pub fn score_line(&self) -> cursor::MoveTo {
+        cursor::MoveTo(self.left, self.top - LINES_ABOVE)
+    }
+
+    /// The start of status line `line` below the board, counting from 0.
+    pub fn below(&self, line: u16) -> cursor::MoveTo {
+        cursor::MoveTo(self.left, self.top + self.height + line)
+    }
+}
+
+/// One character cell of the board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Cell {
+    glyph: char,
+    color: Color,
+}
+
+const BLANK: Cell = Cell {
+    glyph: ' ',
+    color: Color::Reset,
+};
+
+/// Draws the board through a back buffer, so each frame only sends the cells
+/// that changed since the last one.
+///
+/// The board is drawn where the current [`Layout`] puts it; if that moves, as
+/// when the terminal is resized, the whole board is drawn again.
+pub struct Renderer {
+    width: usize,
+    height: usize,
+    theme: Theme,
+    origin: (u16, u16),
+    /// The frame being built.
+    back: Vec<Cell>,
+    /// What is on screen, or `None` if it is unknown, e.g. after a clear.
+    front: Option<Vec<Cell>>,
+}
+
+impl Renderer {
+    /// A renderer for `game`'s board in `theme`. Nothing is assumed to be on
+    /// screen yet.
+    pub fn new(game: &GameState, theme: &Theme) -> Self {
+        let (width, height) = (game.width as usize, game.height as usize);
+        Renderer {
+            width,
+            height,
+            theme: theme.clone(),
+            origin: (0, 1),
+            back: vec![BLANK; width * height],
+            front: None,
+        }
+    }
+
+    /// Forgets what is on screen, so the next flush draws every cell, e.g.
+    /// after the screen was cleared.
+    pub fn invalidate(&mut self) {
+        self.front = None;
+    }
+
+    /// Builds the next frame: walls, obstacles, snakes and food.
+    pub fn draw(&mut self, game: &GameState) {
+        self.back.fill(BLANK);
+
+        // 1) Walls, drawn differently when they wrap around
+        let glyphs = self.theme.glyphs;
+        let border = Cell {
+            glyph: match game.walls {
+                WallMode::Solid => glyphs.wall,
+                WallMode::Wrap => glyphs.wrap_wall,
+            },
+            color: self.theme.walls,
+        };
+        for x in 0..game.width {
+            self.set(Point { x, y: 0 }, border);
+            self.set(Point { x, y: game.height - 1 }, border);
+        }
+        for y in 0..game.height {
+            self.set(Point { x: 0, y }, border);
+            self.set(Point { x: game.width - 1, y }, border);
+        }
+        for &obstacle in &game.obstacles {
+            self.set(obstacle, Cell { glyph: glyphs.wall, color: self.theme.walls });
+        }
+
+        // 2) The snakes, each player in their own colors
+        for (player, snake) in game.snakes.iter().enumerate() {
+            let (head_color, body_color) = self.theme.snake_colors(player);
+            self.draw_snake(snake, head_color, body_color);
+        }
+
+        // 3) The food, in its kind's glyph and color, unless the last one was eaten with no room for another
+        if !game.is_won() {
+            let (glyph, color) = self.theme.food(game.food_kind);
+            self.set(game.food, Cell { glyph, color });
+        }
+    }
+
+    /// Draws one snake; the last element of its body is the head.
+    fn draw_snake(&mut self, snake: &Snake, head_color: Color, body_color: Color) {
+        let glyphs = self.theme.glyphs;
+        let head = snake.head();
+        for &segment in &snake.body {
+            self.set(segment, Cell { glyph: glyphs.body, color: body_color });
+        }
+        // The head stays on top if the snake crossed itself
+        self.set(head, Cell { glyph: glyphs.head, color: head_color });
+    }
+
+    fn set(&mut self, p: Point, cell: Cell) {
+        if p.x >= 0 && p.y >= 0 && (p.x as usize) < self.width && (p.y as usize) < self.height {
+            self.back[p.y as usize * self.width + p.x as usize] = cell;
+        }
+    }
+
+    /// Sends the cells that differ from the screen, batched into one write.
+    pub fn flush(&mut self, stdout: &mut std::io::Stdout, layout: &Layout) -> crossterm::Result<()> {
+        if self.origin != (layout.left, layout.top) {
+            self.origin = (layout.left, layout.top);
+            self.invalidate();
+        }
+
+        let mut out = Vec::new();
+        // Where the cursor and color are after the last thing queued, to skip
+        // repeating them for runs of changed cells
+        let mut at = None;
+        let mut color = None;
+        for (i, &cell) in self.back.iter().enumerate() {
+            if self.front.as_ref().is_some_and(|front| front[i] == cell) {
+                continue;
+            }
+            let column = self.origin.0 + (i % self.width) as u16;
+            let row = self.origin.1 + (i / self.width) as u16;
+            if at != Some((column, row)) {
+                queue!(out, cursor::MoveTo(column, row))?;
+            }
+            if color != Some(cell.color) {
+                queue!(out, SetForegroundColor(cell.color))?;
+                color = Some(cell.color);
+            }
+            queue!(out, Print(cell.glyph))?;
+            at = Some((column + 1, row));
+        }
+        if color.is_some() {
+            // Leave text drawn after the board in the usual color
+            queue!(out, SetForegroundColor(Color::White))?;
+        }
+        stdout.write_all(&out)?;
+        stdout.flush()?;
+
+        match &mut self.front {
+            Some(front) => front.copy_from_slice(&self.back),
+            None => self.front = Some(self.back.clone()),
+        }
+        Ok(())
+    }
+}
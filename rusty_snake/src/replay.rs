@@ -0,0 +1,445 @@
+//! Recording and playing back games.
+//!
+//! A game is fully determined by its settings, its seed and the turns taken,
+//! so a replay only stores those plus the speed changes (for playback timing).
+//! The file format is plain text:
+//!
+//! ```text
+//! rusty_snake replay 4
+//! board 8 8
+//! speed 200
+//! seed 42
+//! walls wrap
+//! players 2
+//! profile arcade 50 steps 5
+//! food 12 3 2 3
+//! level 9
+//! name: Box
+//! ########
+//! #S.....#
+//! #......#
+//! #..##..#
+//! #..##..#
+//! #......#
+//! #......#
+//! ########
+//! 3 U
+//! 10 L
+//! 10 D 2
+//! 12 speed 150
+//! end 57 4 3
+//! ```
+//!
+//! The `walls`, `players`, `profile` and `food` lines are only written for
+//! non-default values. A `profile` line holds the profile's name, fastest
+//! tick, curve and foods per level; its starting tick is the `speed`. A `food`
+//! line holds the regular, bonus, golden and poison spawn weights. A `level` line is
+//! followed by that many lines of the level's map file, so replays of custom
+//! levels are self-contained. The `board` must be a size the game allows, and
+//! match the level's if there is one. Each event line starts with the tick it
+//! applies to, that is the value of `GameState::tick` just before the step it
+//! affects. Turns name the player (counting from 1) after the direction,
+//! unless it is player 1. The `end` line holds the final tick and each
+//! player's score, so playback can be checked against the original run.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::game::{check_board_size, Direction, FoodMix, GameEvent, GameState, Settings, WallMode};
+use crate::level::Level;
+use crate::profile::Profile;
+
+/// The replay format version written by this build. Version 2 places food
+/// differently, version 3 the first food and the speed limits, and version 4
+/// adds speed profiles and the kinds of food, so older replays no longer play
+/// back the same way.
+pub const VERSION: u32 = 4;
+
+const MAGIC: &str = "rusty_snake replay";
+
+/// Where finished games are saved, under the user's data directory.
+pub fn default_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("rusty_snake").join("replays"))
+}
+
+/// A file name for a replay of `game` that sorts by time, e.g. `1700000000-42.replay`.
+pub fn file_name(game: &GameState) -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    format!("{}-{}.replay", secs, game.seed)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// A player (0-based) turned.
+    Turn(usize, Direction),
+    Speed(u64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReplayEvent {
+    pub tick: u64,
+    pub action: Action,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replay {
+    pub settings: Settings,
+    /// Events in tick order.
+    pub events: Vec<ReplayEvent>,
+    /// Final tick and each player's score, if the game was finished.
+    pub end: Option<(u64, Vec<i32>)>,
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(io::Error),
+    /// The file is not a replay, or was written by a newer version.
+    UnsupportedVersion(String),
+    /// A line could not be parsed (1-based line number).
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Io(err) => write!(f, "{}", err),
+            ReplayError::UnsupportedVersion(header) => {
+                write!(f, "not a supported replay file (header `{}`)", header)
+            }
+            ReplayError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<io::Error> for ReplayError {
+    fn from(err: io::Error) -> Self {
+        ReplayError::Io(err)
+    }
+}
+
+impl Replay {
+    /// Starts a new game with the replay's settings.
+    pub fn new_game(&self) -> GameState {
+        GameState::new(&self.settings)
+    }
+
+    pub fn load(path: &Path) -> Result<Replay, ReplayError> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.to_string())
+    }
+}
+
+impl fmt::Display for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} {}", MAGIC, VERSION)?;
+        let settings = &self.settings;
+        writeln!(f, "board {} {}", settings.width, settings.height)?;
+        writeln!(f, "speed {}", settings.speed)?;
+        writeln!(f, "seed {}", settings.seed)?;
+        if settings.walls != WallMode::Solid {
+            writeln!(f, "walls {}", settings.walls.name().to_ascii_lowercase())?;
+        }
+        if settings.players != 1 {
+            writeln!(f, "players {}", settings.players)?;
+        }
+        if let Some(profile) = &settings.profile {
+            writeln!(
+                f,
+                "profile {} {} {} {}",
+                profile.name,
+                profile.min,
+                profile.curve.name(),
+                profile.foods
+            )?;
+        }
+        if settings.food_mix != FoodMix::CLASSIC {
+            let mix = settings.food_mix;
+            writeln!(f, "food {} {} {} {}", mix.regular, mix.bonus, mix.golden, mix.poison)?;
+        }
+        if let Some(level) = &settings.level {
+            let map = level.to_string();
+            writeln!(f, "level {}", map.lines().count())?;
+            write!(f, "{}", map)?;
+        }
+        for event in &self.events {
+            match event.action {
+                Action::Turn(0, direction) => writeln!(f, "{} {}", event.tick, direction_char(direction))?,
+                Action::Turn(player, direction) => {
+                    writeln!(f, "{} {} {}", event.tick, direction_char(direction), player + 1)?
+                }
+                Action::Speed(speed) => writeln!(f, "{} speed {}", event.tick, speed)?,
+            }
+        }
+        if let Some((tick, scores)) = &self.end {
+            write!(f, "end {}", tick)?;
+            for score in scores {
+                write!(f, " {}", score)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for Replay {
+    type Err = ReplayError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines().enumerate().map(|(i, line)| (i + 1, line.trim()));
+
+        let header = lines.next().map(|(_, line)| line).unwrap_or("");
+        if header != format!("{} {}", MAGIC, VERSION) {
+            return Err(ReplayError::UnsupportedVersion(header.to_string()));
+        }
+
+        let mut board = None;
+        let mut speed = None;
+        let mut seed = None;
+        let mut walls = WallMode::Solid;
+        let mut players = 1;
+        let mut profile = None;
+        let mut food_mix = FoodMix::CLASSIC;
+        let mut level = None;
+        let mut events: Vec<ReplayEvent> = Vec::new();
+        let mut end = None;
+
+        while let Some((line_no, line)) = lines.next() {
+            let err = |message: &str| ReplayError::Parse {
+                line: line_no,
+                message: message.to_string(),
+            };
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields.as_slice() {
+                [] => {}
+                ["board", w, h] => board = Some((parse(w, line_no)?, parse(h, line_no)?)),
+                ["speed", ms] => speed = Some(parse(ms, line_no)?),
+                ["seed", n] => seed = Some(parse(n, line_no)?),
+                ["walls", mode] => walls = mode.parse().map_err(|e: String| err(&e))?,
+                ["players", n] => players = parse(n, line_no)?,
+                ["profile", name, min, curve, foods] => {
+                    profile = Some(Profile {
+                        name: name.to_string(),
+                        start: 0,
+                        min: parse(min, line_no)?,
+                        curve: curve.parse().map_err(|e: String| err(&e))?,
+                        foods: parse(foods, line_no)?,
+                    })
+                }
+                ["food", regular, bonus, golden, poison] => {
+                    food_mix = FoodMix {
+                        regular: parse(regular, line_no)?,
+                        bonus: parse(bonus, line_no)?,
+                        golden: parse(golden, line_no)?,
+                        poison: parse(poison, line_no)?,
+                    }
+                }
+                ["level", count] => {
+                    let count: usize = parse(count, line_no)?;
+                    let map: Vec<&str> = lines.by_ref().take(count).map(|(_, line)| line).collect();
+                    if map.len() != count {
+                        return Err(err("the level map is cut short"));
+                    }
+                    let parsed: Level = map.join("\n").parse().map_err(|e| err(&format!("invalid level: {}", e)))?;
+                    level = Some(parsed);
+                }
+                ["end", tick, scores @ ..] => {
+                    let scores = scores
+                        .iter()
+                        .map(|score| parse(score, line_no))
+                        .collect::<Result<_, _>>()?;
+                    end = Some((parse(tick, line_no)?, scores));
+                }
+                [tick, rest @ ..] => {
+                    let tick = parse(tick, line_no)?;
+                    if events.last().is_some_and(|last| last.tick > tick) {
+                        return Err(err("events are out of order"));
+                    }
+                    let action = match rest {
+                        ["speed", ms] => Action::Speed(parse(ms, line_no)?),
+                        [d] => Action::Turn(0, parse_direction(d).ok_or_else(|| err("unknown direction"))?),
+                        [d, player] => {
+                            let player: usize = parse(player, line_no)?;
+                            if player == 0 {
+                                return Err(err("players count from 1"));
+                            }
+                            Action::Turn(player - 1, parse_direction(d).ok_or_else(|| err("unknown direction"))?)
+                        }
+                        _ => return Err(err("unknown event")),
+                    };
+                    events.push(ReplayEvent { tick, action });
+                }
+            }
+        }
+
+        let missing = |what: &str| ReplayError::Parse {
+            line: 1,
+            message: format!("missing `{}` line", what),
+        };
+        let (width, height) = board.ok_or_else(|| missing("board"))?;
+        let speed = speed.ok_or_else(|| missing("speed"))?;
+        if let Some(profile) = &mut profile {
+            profile.start = speed;
+        }
+        // A level checks its own size when it is parsed
+        check_board_size(width, height).map_err(|message| ReplayError::Parse { line: 1, message })?;
+        if let Some(level) = &level {
+            if (level.width, level.height) != (width, height) {
+                return Err(ReplayError::Parse {
+                    line: 1,
+                    message: format!("the board is {}x{}, but the level is {}x{}", width, height, level.width, level.height),
+                });
+            }
+        }
+        Ok(Replay {
+            settings: Settings {
+                width,
+                height,
+                speed,
+                seed: seed.ok_or_else(|| missing("seed"))?,
+                walls,
+                level,
+                players,
+                profile,
+                food_mix,
+            },
+            events,
+            end,
+        })
+    }
+}
+
+fn parse<T: std::str::FromStr>(field: &str, line: usize) -> Result<T, ReplayError> {
+    field.parse().map_err(|_| ReplayError::Parse {
+        line,
+        message: format!("invalid number `{}`", field),
+    })
+}
+
+pub(crate) fn direction_char(direction: Direction) -> char {
+    match direction {
+        Direction::Up => 'U',
+        Direction::Down => 'D',
+        Direction::Left => 'L',
+        Direction::Right => 'R',
+    }
+}
+
+pub(crate) fn parse_direction(s: &str) -> Option<Direction> {
+    match s {
+        "U" => Some(Direction::Up),
+        "D" => Some(Direction::Down),
+        "L" => Some(Direction::Left),
+        "R" => Some(Direction::Right),
+        _ => None,
+    }
+}
+
+/// Records a game as it is played.
+///
+/// Call [`Recorder::observe`] after every step and every speed change.
+#[derive(Debug, Clone)]
+pub struct Recorder {
+    replay: Replay,
+    directions: Vec<Direction>,
+}
+
+impl Recorder {
+    /// Starts recording `game`, freshly created from `settings`.
+    pub fn new(settings: &Settings, game: &GameState) -> Self {
+        Recorder {
+            replay: Replay {
+                settings: settings.clone(),
+                events: Vec::new(),
+                end: None,
+            },
+            directions: game.snakes.iter().map(|snake| snake.direction).collect(),
+        }
+    }
+
+    /// Notes any turn made by the last step and any change of speed.
+    pub fn observe(&mut self, game: &GameState) {
+        for (player, snake) in game.snakes.iter().enumerate() {
+            if snake.direction != self.directions[player] {
+                self.directions[player] = snake.direction;
+                self.replay.events.push(ReplayEvent {
+                    tick: game.tick - 1,
+                    action: Action::Turn(player, snake.direction),
+                });
+            }
+        }
+        if game.speed != self.current_speed() {
+            self.replay.events.push(ReplayEvent {
+                tick: game.tick,
+                action: Action::Speed(game.speed),
+            });
+        }
+    }
+
+    /// Stops recording, stamping the replay with the final tick and scores.
+    pub fn finish(mut self, game: &GameState) -> Replay {
+        let scores = game.snakes.iter().map(|snake| snake.score).collect();
+        self.replay.end = Some((game.tick, scores));
+        self.replay
+    }
+
+    fn current_speed(&self) -> u64 {
+        self.replay
+            .events
+            .iter()
+            .rev()
+            .find_map(|event| match event.action {
+                Action::Speed(speed) => Some(speed),
+                Action::Turn(..) => None,
+            })
+            .unwrap_or(self.replay.settings.speed)
+    }
+}
+
+/// Feeds a replay's recorded events back into a game.
+#[derive(Debug, Clone)]
+pub struct Player<'a> {
+    replay: &'a Replay,
+    next: usize,
+}
+
+impl<'a> Player<'a> {
+    pub fn new(replay: &'a Replay) -> Self {
+        Player { replay, next: 0 }
+    }
+
+    /// Applies the events due at the game's current tick, then steps it.
+    pub fn step(&mut self, game: &mut GameState) -> Vec<GameEvent> {
+        let mut turns = vec![None; game.snakes.len()];
+        while let Some(event) = self.replay.events.get(self.next) {
+            if event.tick > game.tick {
+                break;
+            }
+            if event.tick == game.tick {
+                match event.action {
+                    Action::Turn(player, direction) => {
+                        if let Some(turn) = turns.get_mut(player) {
+                            *turn = Some(direction);
+                        }
+                    }
+                    Action::Speed(speed) => game.speed = speed,
+                }
+            }
+            self.next += 1;
+        }
+        game.step(&turns)
+    }
+}
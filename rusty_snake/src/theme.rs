@@ -0,0 +1,412 @@
+//! Colors and glyphs for the board.
+//!
+//! A few themes are built in, and more can be defined in `themes.json` under
+//! the user's config directory:
+//!
+//! ```text
+//! {
+//!   "default": "dusk",
+//!   "themes": {
+//!     "dusk": {
+//!       "base": "classic",
+//!       "players": [{ "head": "#ffb000", "body": "dark_yellow" }],
+//!       "food": "magenta",
+//!       "poison": "green",
+//!       "walls": "dark_grey",
+//!       "glyphs": { "head": "@", "food": "*", "golden": "$" }
+//!     }
+//!   }
+//! }
+//! ```
+//!
+//! Anything a theme leaves out comes from its `base`, a built-in theme that
+//! defaults to `classic`. Colors are crossterm names (`red`, `dark_grey`, ...),
+//! `#rrggbb`, or a 0-255 palette index.
+
+use crossterm::style::Color;
+use crate::game::FoodKind;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The characters the board is drawn with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Glyphs {
+    pub head: char,
+    pub body: char,
+    /// Regular food.
+    pub food: char,
+    pub bonus: char,
+    pub golden: char,
+    pub poison: char,
+    /// Solid walls and obstacles.
+    pub wall: char,
+    /// Walls that wrap around.
+    pub wrap_wall: char,
+}
+
+impl Glyphs {
+    pub const BLOCKS: Glyphs = Glyphs {
+        head: '█',
+        body: '█',
+        food: '■',
+        bonus: '◆',
+        golden: '★',
+        poison: '×',
+        wall: '#',
+        wrap_wall: '·',
+    };
+
+    /// For terminals or fonts without Unicode block characters.
+    pub const ASCII: Glyphs = Glyphs {
+        head: '@',
+        body: 'o',
+        food: '*',
+        bonus: '+',
+        golden: '$',
+        poison: 'x',
+        wall: '#',
+        wrap_wall: '.',
+    };
+
+    /// These glyphs with each non-ASCII one swapped for its [`Glyphs::ASCII`]
+    /// counterpart.
+    pub fn to_ascii(self) -> Glyphs {
+        let pick = |glyph: char, fallback: char| if glyph.is_ascii() { glyph } else { fallback };
+        Glyphs {
+            head: pick(self.head, Glyphs::ASCII.head),
+            body: pick(self.body, Glyphs::ASCII.body),
+            food: pick(self.food, Glyphs::ASCII.food),
+            bonus: pick(self.bonus, Glyphs::ASCII.bonus),
+            golden: pick(self.golden, Glyphs::ASCII.golden),
+            poison: pick(self.poison, Glyphs::ASCII.poison),
+            wall: pick(self.wall, Glyphs::ASCII.wall),
+            wrap_wall: pick(self.wrap_wall, Glyphs::ASCII.wrap_wall),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub name: String,
+    /// `(head, body)` colors for each player's snake.
+    pub snakes: [(Color, Color); 2],
+    /// Regular food.
+    pub food: Color,
+    pub bonus: Color,
+    pub golden: Color,
+    pub poison: Color,
+    pub walls: Color,
+    pub glyphs: Glyphs,
+}
+
+impl Theme {
+    /// The themes that need no config file.
+    pub fn built_in() -> Vec<Theme> {
+        // Food colors are regular, bonus, golden and poison
+        let theme = |name: &str, snakes, [food, bonus, golden, poison]: [Color; 4], walls, glyphs| Theme {
+            name: name.to_string(),
+            snakes,
+            food,
+            bonus,
+            golden,
+            poison,
+            walls,
+            glyphs,
+        };
+        let rgb = |r, g, b| Color::Rgb { r, g, b };
+        vec![
+            theme(
+                "classic",
+                [(Color::Yellow, Color::Green), (Color::Magenta, Color::Cyan)],
+                [Color::Red, Color::Blue, Color::DarkYellow, Color::DarkMagenta],
+                Color::White,
+                Glyphs::BLOCKS,
+            ),
+            // Bright colors only, and a head that stands out from the body by shape too
+            theme(
+                "high-contrast",
+                [(Color::White, Color::Yellow), (Color::Magenta, Color::Cyan)],
+                [Color::Red, Color::Cyan, Color::Yellow, Color::Green],
+                Color::White,
+                Glyphs { head: '@', ..Glyphs::BLOCKS },
+            ),
+            // No color at all: everything is told apart by its glyph
+            theme(
+                "mono",
+                [(Color::White, Color::White), (Color::Grey, Color::Grey)],
+                [Color::White, Color::White, Color::White, Color::Grey],
+                Color::Grey,
+                Glyphs { head: '@', body: '▓', food: '●', ..Glyphs::BLOCKS },
+            ),
+            // Avoids red against green, for the common kinds of color blindness
+            theme(
+                "colorblind",
+                [(Color::Yellow, Color::Blue), (Color::White, Color::DarkCyan)],
+                [Color::Magenta, Color::Cyan, Color::DarkYellow, Color::Red],
+                Color::Grey,
+                Glyphs::BLOCKS,
+            ),
+            // The Okabe-Ito palette; needs a terminal with 24-bit color
+            theme(
+                "okabe-ito",
+                [
+                    (rgb(240, 228, 66), rgb(0, 114, 178)),
+                    (rgb(204, 121, 167), rgb(86, 180, 233)),
+                ],
+                [rgb(213, 94, 0), rgb(0, 158, 115), rgb(230, 159, 0), rgb(255, 255, 255)],
+                rgb(153, 153, 153),
+                Glyphs::BLOCKS,
+            ),
+        ]
+    }
+
+    /// The head and body colors of `player`'s snake (0-based).
+    pub fn snake_colors(&self, player: usize) -> (Color, Color) {
+        self.snakes[player % self.snakes.len()]
+    }
+
+    /// The glyph and color for food of this kind.
+    pub fn food(&self, kind: FoodKind) -> (char, Color) {
+        match kind {
+            FoodKind::Regular => (self.glyphs.food, self.food),
+            FoodKind::Bonus => (self.glyphs.bonus, self.bonus),
+            FoodKind::Golden => (self.glyphs.golden, self.golden),
+            FoodKind::Poison => (self.glyphs.poison, self.poison),
+        }
+    }
+
+    /// This theme drawn with ASCII characters only.
+    pub fn into_ascii(mut self) -> Theme {
+        self.glyphs = self.glyphs.to_ascii();
+        self
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::built_in().remove(0)
+    }
+}
+
+#[derive(Debug)]
+pub enum ThemeError {
+    Io(io::Error),
+    Parse(serde_json::Error),
+    /// No theme has this name; the names that would work are listed.
+    Unknown { name: String, available: Vec<String> },
+    /// A theme in the file has a color that could not be read.
+    BadColor { theme: String, color: String },
+}
+
+impl fmt::Display for ThemeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThemeError::Io(err) => write!(f, "{}", err),
+            ThemeError::Parse(err) => write!(f, "{}", err),
+            ThemeError::Unknown { name, available } => {
+                write!(f, "no theme named `{}` (try {})", name, available.join(", "))
+            }
+            ThemeError::BadColor { theme, color } => write!(f, "theme `{}`: unknown color `{}`", theme, color),
+        }
+    }
+}
+
+impl std::error::Error for ThemeError {}
+
+impl From<io::Error> for ThemeError {
+    fn from(err: io::Error) -> Self {
+        ThemeError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for ThemeError {
+    fn from(err: serde_json::Error) -> Self {
+        ThemeError::Parse(err)
+    }
+}
+
+/// The built-in themes plus the user's, and which one to use when none is asked for.
+#[derive(Debug, Clone)]
+pub struct Themes {
+    themes: Vec<Theme>,
+    default: String,
+}
+
+impl Default for Themes {
+    fn default() -> Self {
+        let themes = Theme::built_in();
+        let default = themes[0].name.clone();
+        Themes { themes, default }
+    }
+}
+
+impl Themes {
+    /// The default location of the theme file, under the user's config directory.
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("rusty_snake").join("themes.json"))
+    }
+
+    /// The built-in themes plus those in the file at `path`. A missing file
+    /// just gives the built-in ones.
+    pub fn load(path: &Path) -> Result<Themes, ThemeError> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Themes::default()),
+            Err(err) => return Err(err.into()),
+        };
+        let file: ThemeFile = serde_json::from_str(&contents)?;
+
+        let mut themes = Themes::default();
+        for (name, spec) in file.themes {
+            let base = match &spec.base {
+                Some(base) => themes.built_in(base)?,
+                None => Theme::default(),
+            };
+            let theme = spec.apply(&name, base)?;
+            // A user theme may replace a built-in one of the same name
+            themes.themes.retain(|existing| !existing.name.eq_ignore_ascii_case(&name));
+            themes.themes.push(theme);
+        }
+        if let Some(default) = file.default {
+            themes.default = default;
+        }
+        Ok(themes)
+    }
+
+    /// The names of all themes, built-in ones first.
+    pub fn names(&self) -> Vec<String> {
+        self.themes.iter().map(|theme| theme.name.clone()).collect()
+    }
+
+    /// The theme called `name`, or the default one if `name` is `None`.
+    pub fn find(&self, name: Option<&str>) -> Result<Theme, ThemeError> {
+        let name = name.unwrap_or(&self.default);
+        self.themes
+            .iter()
+            .find(|theme| theme.name.eq_ignore_ascii_case(name))
+            .cloned()
+            .ok_or_else(|| ThemeError::Unknown {
+                name: name.to_string(),
+                available: self.names(),
+            })
+    }
+
+    fn built_in(&self, name: &str) -> Result<Theme, ThemeError> {
+        let built_in = Theme::built_in();
+        let available = built_in.iter().map(|theme| theme.name.clone()).collect();
+        built_in
+            .into_iter()
+            .find(|theme| theme.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| ThemeError::Unknown {
+                name: name.to_string(),
+                available,
+            })
+    }
+}
+
+/// The contents of `themes.json`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ThemeFile {
+    #[serde(default)]
+    default: Option<String>,
+    #[serde(default)]
+    themes: BTreeMap<String, ThemeSpec>,
+}
+
+/// A theme as written in the file, where everything is optional.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ThemeSpec {
+    base: Option<String>,
+    /// Player 1's snake, then player 2's.
+    players: Vec<SnakeSpec>,
+    food: Option<String>,
+    bonus: Option<String>,
+    golden: Option<String>,
+    poison: Option<String>,
+    walls: Option<String>,
+    glyphs: GlyphSpec,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct SnakeSpec {
+    head: Option<String>,
+    body: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct GlyphSpec {
+    head: Option<char>,
+    body: Option<char>,
+    food: Option<char>,
+    bonus: Option<char>,
+    golden: Option<char>,
+    poison: Option<char>,
+    wall: Option<char>,
+    wrap_wall: Option<char>,
+}
+
+impl ThemeSpec {
+    /// Overrides `base` with what this spec sets, naming the result `name`.
+    fn apply(self, name: &str, base: Theme) -> Result<Theme, ThemeError> {
+        let color = |color: Option<String>, fallback: Color| match color {
+            None => Ok(fallback),
+            Some(color) => parse_color(&color).ok_or(ThemeError::BadColor {
+                theme: name.to_string(),
+                color,
+            }),
+        };
+
+        let mut snakes = base.snakes;
+        for (i, snake) in self.players.into_iter().enumerate().take(snakes.len()) {
+            snakes[i] = (color(snake.head, snakes[i].0)?, color(snake.body, snakes[i].1)?);
+        }
+        let glyphs = self.glyphs;
+        Ok(Theme {
+            name: name.to_string(),
+            snakes,
+            food: color(self.food, base.food)?,
+            bonus: color(self.bonus, base.bonus)?,
+            golden: color(self.golden, base.golden)?,
+            poison: color(self.poison, base.poison)?,
+            walls: color(self.walls, base.walls)?,
+            glyphs: Glyphs {
+                head: glyphs.head.unwrap_or(base.glyphs.head),
+                body: glyphs.body.unwrap_or(base.glyphs.body),
+                food: glyphs.food.unwrap_or(base.glyphs.food),
+                bonus: glyphs.bonus.unwrap_or(base.glyphs.bonus),
+                golden: glyphs.golden.unwrap_or(base.glyphs.golden),
+                poison: glyphs.poison.unwrap_or(base.glyphs.poison),
+                wall: glyphs.wall.unwrap_or(base.glyphs.wall),
+                wrap_wall: glyphs.wrap_wall.unwrap_or(base.glyphs.wrap_wall),
+            },
+        })
+    }
+}
+
+/// Reads a color name, `#rrggbb` or palette index.
+fn parse_color(s: &str) -> Option<Color> {
+    let s = s.trim();
+    if let Some(hex) = s.strip_prefix('#') {
+        if hex.len() != 6 || !hex.is_ascii() {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        return Some(Color::Rgb {
+            r: channel(0)?,
+            g: channel(2)?,
+            b: channel(4)?,
+        });
+    }
+    if let Ok(index) = s.parse::<u8>() {
+        return Some(Color::AnsiValue(index));
+    }
+    Color::try_from(s).ok()
+}
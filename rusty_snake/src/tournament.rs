@@ -0,0 +1,330 @@
+//! Headless tournaments: many seeded games per bot, with no terminal and no
+//! clock, so they run as fast as the bots can answer.
+//!
+//! Every entrant plays the same seeds, so their results can be compared
+//! directly. Game `i` uses the settings' seed plus `i`.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::bot::{BotController, BotError};
+use crate::controller::{self, AutopilotController, SnakeController};
+use crate::game::{DeathCause, GameState, Settings};
+
+/// The name that enters the built-in AI instead of an external bot.
+pub const AUTOPILOT: &str = "autopilot";
+
+/// Something that can play in a tournament: the built-in AI, or a bot command
+/// started afresh for every game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Entrant {
+    Autopilot,
+    Bot { command: String, timeout: Duration },
+}
+
+impl Entrant {
+    /// `autopilot`, or a bot command.
+    pub fn new(spec: &str, timeout: Duration) -> Self {
+        if spec == AUTOPILOT {
+            Entrant::Autopilot
+        } else {
+            Entrant::Bot {
+                command: spec.to_string(),
+                timeout,
+            }
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Entrant::Autopilot => AUTOPILOT,
+            Entrant::Bot { command, .. } => command,
+        }
+    }
+
+    fn controller(&self) -> Result<Box<dyn SnakeController>, BotError> {
+        Ok(match self {
+            Entrant::Autopilot => Box::new(AutopilotController),
+            Entrant::Bot { command, timeout } => Box::new(BotController::spawn(command, *timeout)?),
+        })
+    }
+}
+
+/// How a tournament game ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ending {
+    Died(DeathCause),
+    /// Still alive when the tick limit was reached.
+    OutOfTime,
+    /// No room was left for more food.
+    FilledBoard,
+}
+
+impl Ending {
+    pub fn label(self) -> &'static str {
+        match self {
+            Ending::Died(cause) => cause.description(),
+            Ending::OutOfTime => "ran out of time",
+            Ending::FilledBoard => "filled the board",
+        }
+    }
+}
+
+/// The result of one game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GameRecord {
+    pub seed: u64,
+    pub score: i32,
+    /// The snake's final length.
+    pub length: usize,
+    /// How many steps the snake survived.
+    pub ticks: u64,
+    pub ending: Ending,
+}
+
+/// Plays one single-player game to the end, or for at most `max_ticks` steps.
+pub fn play(settings: &Settings, controller: &mut dyn SnakeController, max_ticks: u64) -> GameRecord {
+    let mut game = GameState::new(settings);
+
+    let ending = loop {
+        if let Some(cause) = game.snakes[0].death {
+            break Ending::Died(cause);
+        }
+        if game.is_won() {
+            break Ending::FilledBoard;
+        }
+        if game.tick >= max_ticks {
+            break Ending::OutOfTime;
+        }
+        controller::step(&mut game, &mut [controller]);
+    };
+
+    let snake = &game.snakes[0];
+    GameRecord {
+        seed: game.seed,
+        score: snake.score,
+        length: snake.body.len(),
+        ticks: game.tick,
+        ending,
+    }
+}
+
+/// Plays `games` games with each entrant, spread over `jobs` threads, and
+/// returns each entrant's records in seed order.
+pub fn run(
+    settings: &Settings,
+    entrants: &[Entrant],
+    games: u64,
+    max_ticks: u64,
+    jobs: usize,
+) -> Result<Vec<Vec<GameRecord>>, BotError> {
+    let tasks: Vec<(usize, u64)> = (0..entrants.len())
+        .flat_map(|entrant| (0..games).map(move |game| (entrant, game)))
+        .collect();
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(vec![Vec::new(); entrants.len()]);
+    let error = Mutex::new(None);
+
+    thread::scope(|scope| {
+        for _ in 0..jobs.max(1) {
+            scope.spawn(|| {
+                while let Some(&(entrant, game)) = tasks.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    let mut controller = match entrants[entrant].controller() {
+                        Ok(controller) => controller,
+                        Err(err) => {
+                            error.lock().unwrap().get_or_insert(err);
+                            // Skip the remaining games
+                            next.store(tasks.len(), Ordering::Relaxed);
+                            return;
+                        }
+                    };
+                    let mut settings = settings.clone();
+                    settings.seed = settings.seed.wrapping_add(game);
+                    let record = play(&settings, controller.as_mut(), max_ticks);
+                    results.lock().unwrap()[entrant].push(record);
+                }
+            });
+        }
+    });
+
+    if let Some(err) = error.into_inner().unwrap() {
+        return Err(err);
+    }
+    let mut results = results.into_inner().unwrap();
+    for records in &mut results {
+        records.sort_by_key(|record| record.seed);
+    }
+    Ok(results)
+}
+
+/// Mean, median and maximum of one measurement.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Stats {
+    pub mean: f64,
+    pub median: f64,
+    pub max: f64,
+}
+
+impl Stats {
+    fn new(mut values: Vec<f64>) -> Self {
+        if values.is_empty() {
+            return Stats {
+                mean: 0.0,
+                median: 0.0,
+                max: 0.0,
+            };
+        }
+        values.sort_by(f64::total_cmp);
+        let n = values.len();
+        let median = if n % 2 == 1 {
+            values[n / 2]
+        } else {
+            (values[n / 2 - 1] + values[n / 2]) / 2.0
+        };
+        Stats {
+            mean: values.iter().sum::<f64>() / n as f64,
+            median,
+            max: values[n - 1],
+        }
+    }
+}
+
+/// One entrant's results over all its games.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Summary {
+    pub bot: String,
+    pub games: usize,
+    pub score: Stats,
+    pub length: Stats,
+    /// Survival, in ticks.
+    pub ticks: Stats,
+    /// How many games ended each way, by [`Ending::label`].
+    pub endings: BTreeMap<&'static str, usize>,
+}
+
+impl Summary {
+    pub fn new(bot: &str, records: &[GameRecord]) -> Self {
+        let stats = |value: fn(&GameRecord) -> f64| Stats::new(records.iter().map(value).collect());
+        let mut endings = BTreeMap::new();
+        for record in records {
+            *endings.entry(record.ending.label()).or_insert(0) += 1;
+        }
+        Summary {
+            bot: bot.to_string(),
+            games: records.len(),
+            score: stats(|record| record.score as f64),
+            length: stats(|record| record.length as f64),
+            ticks: stats(|record| record.ticks as f64),
+            endings,
+        }
+    }
+}
+
+/// How to print the summaries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    #[default]
+    Table,
+    Csv,
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "table" => Ok(Format::Table),
+            "csv" => Ok(Format::Csv),
+            "json" => Ok(Format::Json),
+            _ => Err(format!("unknown format `{}` (expected table, csv or json)", s)),
+        }
+    }
+}
+
+/// Renders the summaries in `format`.
+pub fn report(summaries: &[Summary], format: Format) -> String {
+    // Every ending any entrant had, so all rows have the same columns
+    let mut labels: Vec<&str> = summaries
+        .iter()
+        .flat_map(|summary| summary.endings.keys().copied())
+        .collect();
+    labels.sort_unstable();
+    labels.dedup();
+    let count = |summary: &Summary, label: &str| summary.endings.get(label).copied().unwrap_or(0);
+
+    let mut out = String::new();
+    match format {
+        Format::Table => {
+            let name_width = summaries.iter().map(|s| s.bot.len()).chain([3]).max().unwrap_or(3);
+            let _ = write!(
+                out,
+                "{:<name_width$}  {:>5}  {:>20}  {:>20}  {:>22}",
+                "Bot", "Games", "Score mean/med/max", "Length mean/med/max", "Ticks mean/med/max"
+            );
+            for label in &labels {
+                let _ = write!(out, "  {}", label);
+            }
+            out.push('\n');
+            let triple = |stats: &Stats| format!("{:.1}/{:.1}/{:.1}", stats.mean, stats.median, stats.max);
+            for summary in summaries {
+                let _ = write!(
+                    out,
+                    "{:<name_width$}  {:>5}  {:>20}  {:>20}  {:>22}",
+                    summary.bot,
+                    summary.games,
+                    triple(&summary.score),
+                    triple(&summary.length),
+                    triple(&summary.ticks)
+                );
+                for label in &labels {
+                    let _ = write!(out, "  {:>width$}", count(summary, label), width = label.len());
+                }
+                out.push('\n');
+            }
+        }
+        Format::Csv => {
+            out.push_str("bot,games");
+            for measure in ["score", "length", "ticks"] {
+                for stat in ["mean", "median", "max"] {
+                    let _ = write!(out, ",{}_{}", measure, stat);
+                }
+            }
+            for label in &labels {
+                let _ = write!(out, ",{}", label);
+            }
+            out.push('\n');
+            for summary in summaries {
+                let _ = write!(out, "{},{}", csv_field(&summary.bot), summary.games);
+                for stats in [&summary.score, &summary.length, &summary.ticks] {
+                    let _ = write!(out, ",{},{},{}", stats.mean, stats.median, stats.max);
+                }
+                for label in &labels {
+                    let _ = write!(out, ",{}", count(summary, label));
+                }
+                out.push('\n');
+            }
+        }
+        Format::Json => {
+            out = serde_json::to_string_pretty(summaries).expect("summaries serialize");
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Quotes a CSV field if it needs it.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
@@ -0,0 +1,59 @@
+use crossterm::{
+    cursor,
+    event::{KeyCode, KeyEvent, KeyModifiers},
+    execute,
+    style::ResetColor,
+    terminal::{self, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
+
+/// Puts the terminal into the state the game needs, and puts it back when
+/// dropped, even if the game returns early with an error.
+///
+/// The game runs on the alternate screen in raw mode with the cursor hidden,
+/// so the shell's screen is left as it was.
+pub struct TerminalGuard(());
+
+impl TerminalGuard {
+    pub fn enter(stdout: &mut std::io::Stdout) -> crossterm::Result<Self> {
+        install_panic_hook();
+        terminal::enable_raw_mode()?;
+        let guard = TerminalGuard(());
+        execute!(stdout, EnterAlternateScreen, terminal::Clear(ClearType::All), cursor::Hide)?;
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore();
+    }
+}
+
+/// Leaves raw mode and the alternate screen, and shows the cursor in the
+/// default colors. Errors are ignored, since this runs while giving up anyway.
+pub fn restore() {
+    let _ = execute!(std::io::stdout(), ResetColor, cursor::Show, LeaveAlternateScreen);
+    let _ = terminal::disable_raw_mode();
+}
+
+/// Restores the terminal before a panic message is printed, so the message is
+/// readable and the shell still works afterwards.
+fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        restore();
+        default_hook(info);
+    }));
+}
+
+/// Whether the key is Ctrl+C, which raw mode delivers as a key press rather
+/// than a signal.
+pub fn is_interrupt(key_event: &KeyEvent) -> bool {
+    key_event.code == KeyCode::Char('c') && key_event.modifiers.contains(KeyModifiers::CONTROL)
+}
+
+/// Ctrl+C before a game has started: restores the terminal and exits.
+pub fn exit_interrupted() -> ! {
+    restore();
+    std::process::exit(130);
+}
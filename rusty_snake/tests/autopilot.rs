@@ -0,0 +1,68 @@
+//! The autopilot's planning around a snake that is still growing.
+
+mod common;
+
+use rusty_snake::autopilot;
+use rusty_snake::game::{Direction, GameState, Point};
+
+/// A 16x9 board walled off into a ring of corridor around a pillar, and a room
+/// reached through a single door from the ring's top-right corner. The snake
+/// fills most of the ring with its head at that corner, heading Right for
+/// the door, and the food sits two cells down the ring. Going for the food
+/// leaves no way out but through the tail, so it is only safe if the tail
+/// moves out of the way in time.
+fn ring_and_room(growing: u32) -> GameState {
+    let mut game = GameState::new(&common::settings(16, 9, 1));
+    let p = |x, y| Point { x, y };
+
+    // The pillar inside the ring, the wall below it, and the wall between
+    // the ring and the room, with a door at the top
+    game.obstacles.clear();
+    game.obstacles.extend((2..=4).flat_map(|x| (2..=3).map(move |y| p(x, y))));
+    game.obstacles.extend((1..=5).flat_map(|x| (5..=7).map(move |y| p(x, y))));
+    game.obstacles.extend((2..=7).map(|y| p(6, y)));
+
+    // Tail first: up the left side of the ring and along the top to the corner
+    let body = [
+        p(3, 4),
+        p(2, 4),
+        p(1, 4),
+        p(1, 3),
+        p(1, 2),
+        p(1, 1),
+        p(2, 1),
+        p(3, 1),
+        p(4, 1),
+        p(5, 1),
+    ];
+    let snake = &mut game.snakes[0];
+    snake.body = body.into_iter().collect();
+    snake.direction = Direction::Right;
+    snake.next_direction = Direction::Right;
+    snake.growing = growing;
+    game.snake_positions = body.into_iter().map(|cell| (cell, 0)).collect();
+    game.food = p(5, 3);
+    game
+}
+
+#[test]
+fn goes_for_the_food_when_the_tail_clears_the_way() {
+    let game = ring_and_room(0);
+    assert_eq!(autopilot::choose_direction(&game, 0), Some(Direction::Down));
+}
+
+#[test]
+fn leaves_the_ring_while_still_growing() {
+    let game = ring_and_room(4);
+    assert_eq!(autopilot::choose_direction(&game, 0), Some(Direction::Right));
+}
+
+#[test]
+fn a_growing_snake_steered_by_the_autopilot_survives() {
+    let mut game = ring_and_room(4);
+    for _ in 0..30 {
+        let direction = autopilot::choose_direction(&game, 0);
+        game.step(&[direction]);
+        assert!(game.snakes[0].is_alive(), "died on tick {}", game.tick);
+    }
+}
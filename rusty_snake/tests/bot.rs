@@ -0,0 +1,110 @@
+//! Bots run as subprocesses: their answers, and forfeiting when they misbehave.
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use common::ScratchDir;
+use rusty_snake::bot::{parse_reply, BotController, BotError};
+use rusty_snake::controller::{self, AutopilotController, SnakeController};
+use rusty_snake::game::{DeathCause, Direction, GameState, GameStatus, Settings};
+
+const TIMEOUT: Duration = Duration::from_millis(200);
+
+/// Starts a bot running the shell `script`, saved in `dir`.
+fn script_bot(dir: &Path, script: &str) -> BotController {
+    let path = dir.join("bot.sh");
+    fs::write(&path, script).unwrap();
+    BotController::spawn(&format!("sh {}", path.display()), TIMEOUT).unwrap()
+}
+
+/// Plays player 1 with `bot` against the autopilot for up to `ticks` steps.
+fn play(bot: &mut BotController, ticks: u64) -> GameState {
+    let mut game = GameState::new(&Settings {
+        players: 2,
+        ..common::settings(40, 20, 1)
+    });
+    let mut rival = AutopilotController;
+    while game.tick < ticks && !game.is_over() {
+        controller::step(&mut game, &mut [bot, &mut rival]);
+    }
+    game
+}
+
+/// Plays `bot` until it forfeits, checking the game ends on its account.
+fn play_to_forfeit(bot: &mut BotController) {
+    let game = play(bot, 10);
+    assert!(bot.has_forfeited());
+    assert_eq!(game.status, GameStatus::Over);
+    assert_eq!(game.snakes[0].death, Some(DeathCause::Forfeit));
+    assert_eq!(game.snakes[1].death, None);
+}
+
+#[test]
+fn replies_may_be_json_or_bare_words() {
+    assert_eq!(parse_reply("\"up\"").unwrap(), Direction::Up);
+    assert_eq!(parse_reply("  Left \n").unwrap(), Direction::Left);
+    assert_eq!(parse_reply("DOWN").unwrap(), Direction::Down);
+    assert!(matches!(parse_reply("north"), Err(BotError::InvalidReply(reply)) if reply == "north"));
+    assert!(matches!(parse_reply(""), Err(BotError::InvalidReply(_))));
+}
+
+#[test]
+fn a_bot_that_answers_steers_its_snake() {
+    let dir = ScratchDir::new("bot_answers");
+    let board = dir.join("board.json");
+    let script = format!(
+        "read line\necho \"$line\" > {}\necho '\"down\"'\nwhile read line; do echo down; done\n",
+        board.display()
+    );
+    let mut bot = script_bot(&dir, &script);
+
+    let game = play(&mut bot, 3);
+
+    assert!(bot.forfeit_reason().is_none(), "{:?}", bot.forfeit_reason());
+    assert_eq!(game.tick, 3);
+    assert_eq!(game.snakes[0].direction, Direction::Down);
+    let sent: serde_json::Value = serde_json::from_str(&fs::read_to_string(&board).unwrap()).unwrap();
+    assert_eq!(sent["tick"], 0);
+    assert_eq!(sent["width"], 40);
+    assert_eq!(sent["walls"], "solid");
+    assert_eq!(sent["you"], 0);
+    assert_eq!(sent["food_kind"], "regular");
+    assert_eq!(sent["snakes"].as_array().unwrap().len(), 2);
+    assert_eq!(sent["snakes"][0]["body"].as_array().unwrap().last(), Some(&sent["snakes"][0]["head"]));
+}
+
+#[test]
+fn a_slow_bot_forfeits() {
+    let dir = ScratchDir::new("bot_slow");
+    let mut bot = script_bot(&dir, "read line\necho right\nexec sleep 5\n");
+    play_to_forfeit(&mut bot);
+    assert!(matches!(bot.forfeit_reason(), Some(BotError::TimedOut(timeout)) if *timeout == TIMEOUT));
+}
+
+#[test]
+fn a_bot_answering_nonsense_forfeits() {
+    let dir = ScratchDir::new("bot_nonsense");
+    let mut bot = script_bot(&dir, "read line\necho sideways\n");
+    play_to_forfeit(&mut bot);
+    assert!(matches!(bot.forfeit_reason(), Some(BotError::InvalidReply(reply)) if reply == "sideways"));
+}
+
+#[test]
+fn a_bot_that_exits_forfeits() {
+    let dir = ScratchDir::new("bot_exits");
+    let mut bot = script_bot(&dir, "read line\necho right\n");
+    play_to_forfeit(&mut bot);
+    assert!(matches!(bot.forfeit_reason(), Some(BotError::Exited)));
+}
+
+#[test]
+fn a_bot_that_cannot_start_is_an_error() {
+    assert!(matches!(BotController::spawn("", TIMEOUT), Err(BotError::Spawn(_))));
+    assert!(matches!(
+        BotController::spawn("rusty_snake_no_such_bot --fast", TIMEOUT),
+        Err(BotError::Spawn(_))
+    ));
+}
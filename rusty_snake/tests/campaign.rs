@@ -0,0 +1,89 @@
+//! Campaign progress: levels, lives and the save file.
+
+mod common;
+
+use std::fs;
+
+use common::ScratchDir;
+use rusty_snake::campaign::{Campaign, DEFAULT_TARGET, STARTING_LIVES};
+use rusty_snake::level::Level;
+
+#[test]
+fn a_new_campaign_starts_on_the_first_level() {
+    let campaign = Campaign::new();
+    assert_eq!((campaign.level, campaign.lives, campaign.total_score), (0, STARTING_LIVES, 0));
+    assert_eq!(Campaign::level_count(), Level::bundled_names().count());
+    assert_eq!(campaign.current_level(), Level::bundled("pillars").unwrap());
+    assert_eq!(campaign.target(), campaign.current_level().target.unwrap_or(DEFAULT_TARGET));
+}
+
+#[test]
+fn completing_every_level_finishes_the_campaign() {
+    let mut campaign = Campaign::new();
+    for level in 1..Campaign::level_count() {
+        assert!(campaign.complete_level(10));
+        assert_eq!(campaign.level, level);
+    }
+    assert!(!campaign.complete_level(10));
+    assert!(campaign.is_finished());
+    assert_eq!(campaign.total_score, 10 * Campaign::level_count() as i32);
+}
+
+#[test]
+fn losing_every_life_ends_the_campaign() {
+    let mut campaign = Campaign::new();
+    for _ in 1..STARTING_LIVES {
+        assert!(campaign.lose_life());
+    }
+    assert!(!campaign.lose_life());
+    assert!(campaign.is_over());
+    assert!(!campaign.lose_life());
+    assert_eq!(campaign.lives, 0);
+}
+
+#[test]
+fn saved_progress_loads_back() {
+    let dir = ScratchDir::new("campaign_round_trip");
+    let path = dir.join("nested").join("campaign.json");
+    let mut campaign = Campaign::new();
+    campaign.complete_level(12);
+    campaign.lose_life();
+
+    campaign.save(&path).unwrap();
+
+    assert_eq!(Campaign::load(&path), Some(campaign));
+}
+
+#[test]
+fn ended_runs_and_bad_files_do_not_resume() {
+    let dir = ScratchDir::new("campaign_ended");
+    let path = dir.join("campaign.json");
+    assert_eq!(Campaign::load(&path), None);
+
+    let mut over = Campaign::new();
+    while over.lose_life() {}
+    over.save(&path).unwrap();
+    assert_eq!(Campaign::load(&path), None);
+
+    let mut finished = Campaign::new();
+    while finished.complete_level(1) {}
+    finished.save(&path).unwrap();
+    assert_eq!(Campaign::load(&path), None);
+
+    fs::write(&path, "{ not json").unwrap();
+    assert_eq!(Campaign::load(&path), None);
+}
+
+#[test]
+fn clearing_removes_the_save() {
+    let dir = ScratchDir::new("campaign_clear");
+    let path = dir.join("campaign.json");
+    Campaign::new().save(&path).unwrap();
+
+    Campaign::clear(&path).unwrap();
+    assert!(!path.exists());
+    assert_eq!(Campaign::load(&path), None);
+
+    // Clearing with nothing saved is fine
+    Campaign::clear(&path).unwrap();
+}
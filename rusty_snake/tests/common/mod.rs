@@ -0,0 +1,99 @@
+//! Helpers shared by the integration tests.
+
+// Each test file uses only some of these
+#![allow(dead_code)]
+
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rusty_snake::game::{Difficulty, FoodMix, Settings, WallMode};
+use rusty_snake::level::Level;
+
+/// A one-player game on a `width` x `height` board with solid walls, at
+/// normal speed with classic food. Override the rest with struct update syntax.
+pub fn settings(width: i32, height: i32, seed: u64) -> Settings {
+    Settings {
+        width,
+        height,
+        speed: Difficulty::Normal.speed(),
+        seed,
+        walls: WallMode::Solid,
+        level: None,
+        players: 1,
+        profile: None,
+        food_mix: FoodMix::CLASSIC,
+    }
+}
+
+/// [`settings`] for a game on `level`, whose map sets the board size.
+pub fn level_settings(level: Level, seed: u64) -> Settings {
+    let board = settings(level.width, level.height, seed);
+    Settings {
+        level: Some(level),
+        ..board
+    }
+}
+
+/// An empty directory under the system's temp directory, removed again when
+/// dropped.
+#[derive(Debug)]
+pub struct ScratchDir(PathBuf);
+
+impl ScratchDir {
+    /// A fresh directory named after `test`.
+    pub fn new(test: &str) -> Self {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "rusty_snake_{}_{}_{}",
+            test,
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        ScratchDir(dir)
+    }
+}
+
+impl Deref for ScratchDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Runs the binary with its config directory holding `files`, each a name
+/// under `rusty_snake/` and its contents. A one-game headless tournament stands
+/// in for a game: it only starts once the theme and key bindings have loaded,
+/// so a bad config fails it the way it would fail a game.
+pub fn run_with_config(files: &[(&str, &str)], args: &[&str]) -> Output {
+    let dir = ScratchDir::new("config");
+    fs::create_dir_all(dir.join("rusty_snake")).unwrap();
+    for (name, contents) in files {
+        fs::write(dir.join("rusty_snake").join(name), contents).unwrap();
+    }
+    Command::new(env!("CARGO_BIN_EXE_rusty_snake"))
+        .env("HOME", &*dir)
+        .env("XDG_CONFIG_HOME", &*dir)
+        .env("XDG_DATA_HOME", &*dir)
+        .args(args)
+        .args(["--width", "10", "--height", "10"])
+        .args(["tournament", "--bot", "autopilot", "--games", "1", "--max-ticks", "20"])
+        .output()
+        .unwrap()
+}
+
+/// What a run printed on stderr, or `None` if it succeeded.
+pub fn run_error(output: &Output) -> Option<String> {
+    (!output.status.success()).then(|| String::from_utf8_lossy(&output.stderr).trim().to_string())
+}
@@ -0,0 +1,210 @@
+//! The training environment: episodes, rewards, observations and the danger
+//! features agents learn from.
+
+use rusty_snake::autopilot;
+use rusty_snake::env::{Action, EnvConfig, Encoding, Rewards, SnakeEnv};
+use rusty_snake::game::{DeathCause, Direction, Point};
+use rusty_snake::level::Level;
+
+/// An environment on the default 20x10 board with the given encoding.
+fn new_env(encoding: Encoding) -> SnakeEnv {
+    SnakeEnv::new(EnvConfig {
+        encoding,
+        ..EnvConfig::default()
+    })
+    .unwrap()
+}
+
+/// The value at `cell` in channel `channel` of a grid observation.
+fn at(data: &[f32], channel: usize, cell: Point) -> f32 {
+    data[channel * 20 * 10 + cell.y as usize * 20 + cell.x as usize]
+}
+
+/// Picks the safe relative action that heads for the food, preferring to go
+/// straight, or goes straight when nothing is safe.
+fn greedy(features: &[f32]) -> Action {
+    let wanted = [1.0 - features[4], features[5], features[6]];
+    (0..3)
+        .filter(|&i| features[i] == 0.0)
+        .max_by(|&a, &b| wanted[a].total_cmp(&wanted[b]).then(b.cmp(&a)))
+        .map_or(Action::Straight, |i| Action::RELATIVE[i])
+}
+
+#[test]
+fn boards_outside_the_size_limits_are_refused() {
+    for (width, height) in [(2, 2), (3, 10), (20, 7), (501, 10)] {
+        let message = SnakeEnv::new(EnvConfig {
+            width,
+            height,
+            ..EnvConfig::default()
+        })
+        .unwrap_err();
+        assert!(
+            message.starts_with(&format!("a {}x{} board is not allowed", width, height)),
+            "{}",
+            message
+        );
+    }
+    // A level's own size is what counts
+    let env = SnakeEnv::new(EnvConfig {
+        width: 0,
+        height: 0,
+        level: Some(Level::bundled("pillars").unwrap()),
+        ..EnvConfig::default()
+    })
+    .unwrap();
+    assert_eq!(env.game().width, Level::bundled("pillars").unwrap().width);
+}
+
+#[test]
+fn reset_starts_a_fresh_episode() {
+    let mut env = new_env(Encoding::Grid);
+    let observation = env.reset(7);
+
+    assert_eq!(observation.shape, vec![4, 10, 20]);
+    assert_eq!(observation.data.len(), 4 * 10 * 20);
+    let game = env.game();
+    assert_eq!(game.tick, 0);
+    assert_eq!(game.snakes[0].body.len(), 1);
+    let head = game.snakes[0].head();
+    assert_eq!(at(&observation.data, 0, head), 1.0);
+    assert_eq!(at(&observation.data, 1, head), 1.0);
+    assert_eq!(at(&observation.data, 2, game.food), 1.0);
+    assert_eq!(at(&observation.data, 3, Point { x: 0, y: 0 }), 1.0);
+    assert_eq!(at(&observation.data, 3, Point { x: 19, y: 9 }), 1.0);
+    assert_eq!(observation.data.iter().filter(|&&value| value != 0.0).count(), 2 + 1 + 2 * 20 + 2 * 8);
+}
+
+#[test]
+fn the_same_seed_plays_the_same_episode() {
+    let play = |seed| {
+        let mut env = new_env(Encoding::Features);
+        let mut observation = env.reset(seed);
+        let mut steps = Vec::new();
+        loop {
+            let (next, reward, done, info) = env.step(greedy(&observation.data));
+            steps.push((next.data.clone(), reward, info));
+            observation = next;
+            if done {
+                return steps;
+            }
+        }
+    };
+    assert_eq!(play(3), play(3));
+}
+
+#[test]
+fn eating_earns_the_food_and_step_rewards() {
+    let mut env = SnakeEnv::new(EnvConfig {
+        rewards: Rewards {
+            food: 2.0,
+            death: -5.0,
+            step: -0.25,
+        },
+        ..EnvConfig::default()
+    })
+    .unwrap();
+    env.reset(1);
+    let (head, food) = (env.game().snakes[0].head(), env.game().food);
+    assert!(food.x > head.x && food.y > head.y, "the first food is ahead and below");
+
+    let mut moves = vec![Direction::Down; (food.y - head.y) as usize];
+    moves.extend(vec![Direction::Right; (food.x - head.x) as usize]);
+    let last = moves.len() - 1;
+    for (i, direction) in moves.into_iter().enumerate() {
+        let (_, reward, done, info) = env.step(Action::Go(direction));
+        assert!(!done);
+        assert_eq!(info.tick, i as u64 + 1);
+        if i == last {
+            assert!(info.ate);
+            assert_eq!(reward, 2.0 - 0.25);
+            assert_eq!((info.score, info.length), (1, 2));
+        } else {
+            assert!(!info.ate);
+            assert_eq!(reward, -0.25);
+        }
+    }
+}
+
+#[test]
+fn hitting_the_wall_ends_the_episode() {
+    let mut env = new_env(Encoding::Grid);
+    env.reset(0);
+    let (reward, info) = loop {
+        let (_, reward, done, info) = env.step(Action::Go(Direction::Up));
+        if done {
+            break (reward, info);
+        }
+    };
+    assert_eq!(reward, -1.0);
+    assert_eq!(info.death, Some(DeathCause::Wall));
+    assert!(!info.truncated);
+
+    // Nothing changes until the next reset
+    let tick = env.game().tick;
+    let (_, reward, done, info) = env.step(Action::Straight);
+    assert_eq!((reward, done, info.tick), (0.0, true, tick));
+
+    env.reset(0);
+    let (_, _, done, info) = env.step(Action::Straight);
+    assert!(!done);
+    assert_eq!(info.tick, 1);
+}
+
+#[test]
+fn running_out_of_steps_truncates() {
+    let mut env = SnakeEnv::new(EnvConfig {
+        max_steps: Some(3),
+        ..EnvConfig::default()
+    })
+    .unwrap();
+    env.reset(0);
+    for _ in 0..2 {
+        assert!(!env.step(Action::Straight).2);
+    }
+    let (_, reward, done, info) = env.step(Action::Straight);
+    assert!(done);
+    assert!(info.truncated);
+    assert_eq!(info.death, None);
+    assert_eq!(reward, 0.0);
+}
+
+#[test]
+fn features_flag_exactly_the_moves_that_kill() {
+    let mut tail_checks = 0;
+    for seed in 0..4 {
+        let mut env = SnakeEnv::new(EnvConfig {
+            encoding: Encoding::Features,
+            max_steps: Some(300),
+            ..EnvConfig::default()
+        })
+        .unwrap();
+        let mut observation = env.reset(seed);
+        loop {
+            assert_eq!(observation.shape, vec![11]);
+            let snake = &env.game().snakes[0];
+            let heading = snake.direction;
+            assert_eq!(observation.data[7 + Direction::ALL.iter().position(|&d| d == heading).unwrap()], 1.0);
+
+            for (i, action) in Action::RELATIVE.into_iter().enumerate() {
+                let cell = env.game().next_cell(snake.head(), action.direction(heading));
+                if snake.body.len() > 1 && cell == snake.body.front().copied() {
+                    tail_checks += 1;
+                }
+                // Try the move on a copy and see if it kills
+                let mut trial = env.clone();
+                let dies = trial.step(action).3.death.is_some();
+                assert_eq!(observation.data[i] == 1.0, dies, "seed {} tick {} {:?}", seed, env.game().tick, action);
+            }
+
+            // The autopilot chases its tail, so the tail is often right there
+            let action = autopilot::choose_direction(env.game(), 0).map_or(Action::Straight, Action::Go);
+            let (next, _, done, _) = env.step(action);
+            if done {
+                break;
+            }
+            observation = next;
+        }
+    }
+    assert!(tail_checks > 0, "no episode put the tail next to the head");
+}
@@ -0,0 +1,86 @@
+//! Food kinds: what eating each one does to the snake.
+
+mod common;
+
+use rusty_snake::game::{Direction, FoodKind, FoodMix, GameState, Point, Settings};
+
+/// A game whose snake, heading right, is `length` cells long.
+fn game_with_length(length: usize) -> GameState {
+    let mut game = GameState::new(&Settings {
+        food_mix: FoodMix::MIXED,
+        ..common::settings(40, 20, 1)
+    });
+    game.snakes[0].direction = Direction::Right;
+    game.snakes[0].next_direction = Direction::Right;
+    for _ in 1..length {
+        set_food(&mut game, FoodKind::Regular);
+        game.step(&[None]);
+    }
+    assert_eq!(game.snakes[0].body.len(), length);
+    game
+}
+
+/// Puts food of `kind` just ahead of the snake.
+fn set_food(game: &mut GameState, kind: FoodKind) {
+    let head = game.snakes[0].head();
+    game.food = Point { x: head.x + 1, y: head.y };
+    game.food_kind = kind;
+    game.food_expires = None;
+}
+
+/// Moves the snake `ticks` times without letting it find food.
+fn coast(game: &mut GameState, ticks: usize) {
+    for _ in 0..ticks {
+        game.food = Point { x: 0, y: 0 };
+        game.step(&[None]);
+    }
+}
+
+#[test]
+fn golden_food_grows_over_the_next_moves() {
+    let mut game = game_with_length(3);
+    set_food(&mut game, FoodKind::Golden);
+    game.step(&[None]);
+    assert_eq!((game.snakes[0].body.len(), game.snakes[0].growing), (4, 2));
+
+    coast(&mut game, 3);
+    assert_eq!((game.snakes[0].body.len(), game.snakes[0].growing), (6, 0));
+}
+
+#[test]
+fn poison_takes_growth_still_to_come_first() {
+    let mut game = game_with_length(3);
+    set_food(&mut game, FoodKind::Golden);
+    game.step(&[None]);
+
+    // Both pending segments go, and the body keeps its length
+    set_food(&mut game, FoodKind::Poison);
+    game.step(&[None]);
+    assert_eq!((game.snakes[0].body.len(), game.snakes[0].growing), (4, 0));
+
+    coast(&mut game, 3);
+    assert_eq!(game.snakes[0].body.len(), 4);
+}
+
+#[test]
+fn poison_takes_what_it_cannot_cancel_from_the_body() {
+    let mut game = game_with_length(5);
+    set_food(&mut game, FoodKind::Golden);
+    game.step(&[None]);
+    coast(&mut game, 1);
+    assert_eq!((game.snakes[0].body.len(), game.snakes[0].growing), (7, 1));
+
+    set_food(&mut game, FoodKind::Poison);
+    game.step(&[None]);
+    assert_eq!((game.snakes[0].body.len(), game.snakes[0].growing), (6, 0));
+}
+
+#[test]
+fn poison_never_takes_the_head() {
+    let mut game = game_with_length(2);
+    set_food(&mut game, FoodKind::Poison);
+    game.step(&[None]);
+    assert_eq!(game.snakes[0].body.len(), 1);
+    assert_eq!(game.snakes[0].score, 0);
+    assert!(!game.is_over());
+}
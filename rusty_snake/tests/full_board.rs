@@ -0,0 +1,120 @@
+//! Food placement and the win on boards with little or no room left.
+
+mod common;
+
+use rusty_snake::game::{Direction, GameEvent, GameState, Point};
+
+/// A one-player game on a `size` x `size` board with solid walls.
+fn new_game(size: i32, seed: u64) -> GameState {
+    GameState::new(&common::settings(size, size, seed))
+}
+
+/// Every cell inside the walls, snaking left to right, then right to left on
+/// the next row, so consecutive cells are always neighbours.
+fn serpentine(game: &GameState) -> Vec<Point> {
+    (1..game.height - 1)
+        .flat_map(|y| {
+            let row: Vec<Point> = (1..game.width - 1).map(|x| Point { x, y }).collect();
+            if y % 2 == 1 {
+                row
+            } else {
+                row.into_iter().rev().collect()
+            }
+        })
+        .collect()
+}
+
+/// Lays the snake along `body`, tail first, heading towards `heading`.
+fn place_snake(game: &mut GameState, body: &[Point], heading: Direction) {
+    let snake = &mut game.snakes[0];
+    snake.body = body.iter().copied().collect();
+    snake.direction = heading;
+    snake.next_direction = heading;
+    game.snake_positions = body.iter().map(|&cell| (cell, 0)).collect();
+}
+
+/// The way from `from` to its neighbour `to`.
+fn towards(from: Point, to: Point) -> Direction {
+    match (to.x - from.x, to.y - from.y) {
+        (0, -1) => Direction::Up,
+        (0, 1) => Direction::Down,
+        (-1, 0) => Direction::Left,
+        (1, 0) => Direction::Right,
+        _ => panic!("{:?} and {:?} are not neighbours", from, to),
+    }
+}
+
+/// A snake along all but the last cells of the path, with the food just ahead
+/// of its head and the cells after that left free.
+fn nearly_full(size: i32, seed: u64, free: usize) -> GameState {
+    let mut game = new_game(size, seed);
+    let path = serpentine(&game);
+    let length = path.len() - free - 1;
+    place_snake(&mut game, &path[..length], towards(path[length - 1], path[length]));
+    game.food = path[length];
+    game
+}
+
+#[test]
+fn eating_the_last_free_cell_wins() {
+    let mut game = nearly_full(5, 1, 0);
+    let length = game.snakes[0].body.len();
+
+    let events = game.step(&[None]);
+
+    assert!(game.is_won());
+    assert!(game.is_over());
+    assert!(events.contains(&GameEvent::BoardFilled));
+    assert!(!events.iter().any(|event| matches!(event, GameEvent::FoodSpawned(_))));
+    let snake = &game.snakes[0];
+    assert!(snake.is_alive());
+    assert_eq!(snake.body.len(), length + 1);
+    assert_eq!(snake.score, 1);
+    assert_eq!(game.winner(), None);
+}
+
+#[test]
+fn nothing_moves_after_a_win() {
+    let mut game = nearly_full(5, 1, 0);
+    game.step(&[None]);
+    let (body, tick) = (game.snakes[0].body.clone(), game.tick);
+
+    assert!(game.step(&[Some(Direction::Up)]).is_empty());
+    assert_eq!(game.snakes[0].body, body);
+    assert_eq!(game.tick, tick);
+}
+
+#[test]
+fn food_goes_to_the_only_free_cell() {
+    for seed in 0..20 {
+        let mut game = nearly_full(5, seed, 1);
+        let last = *serpentine(&game).last().unwrap();
+
+        let events = game.step(&[None]);
+
+        assert!(!game.is_over());
+        assert_eq!(game.food, last);
+        assert!(events.contains(&GameEvent::FoodSpawned(last)));
+    }
+}
+
+#[test]
+fn food_never_lands_on_the_snake_or_an_obstacle() {
+    for seed in 0..200 {
+        for free in 1..4 {
+            let mut game = nearly_full(6, seed, free);
+            let path = serpentine(&game);
+            // Wall off one of the free cells whenever that still leaves one
+            if free > 1 {
+                game.obstacles.insert(path[path.len() - 1]);
+            }
+
+            game.step(&[None]);
+
+            assert!(!game.is_over(), "seed {}", seed);
+            assert!(!game.snake_positions.contains_key(&game.food), "seed {}", seed);
+            assert!(!game.obstacles.contains(&game.food), "seed {}", seed);
+            assert!(path.contains(&game.food), "seed {}", seed);
+        }
+    }
+}
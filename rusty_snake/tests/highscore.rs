@@ -0,0 +1,110 @@
+//! High-score tables: ranking, table names and the file on disk.
+
+mod common;
+
+use std::fs;
+
+use common::ScratchDir;
+use rusty_snake::game::{Difficulty, FoodMix, Settings, WallMode};
+use rusty_snake::highscore::{Entry, HighScores, TABLE_SIZE};
+use rusty_snake::level::Level;
+use rusty_snake::profile::Profile;
+
+fn entry(name: &str, score: i32) -> Entry {
+    Entry {
+        name: name.to_string(),
+        score,
+        seed: 7,
+    }
+}
+
+fn settings() -> Settings {
+    common::settings(40, 20, 0)
+}
+
+#[test]
+fn a_corrupt_file_is_moved_aside() {
+    let dir = ScratchDir::new("highscore_corrupt");
+    let path = dir.join("highscores.json");
+    fs::write(&path, "{ not json").unwrap();
+
+    let scores = HighScores::load(&path);
+
+    assert!(scores.table("40x20 Normal").is_empty());
+    assert!(!path.exists());
+    assert_eq!(fs::read_to_string(dir.join("highscores.json.bak")).unwrap(), "{ not json");
+}
+
+#[test]
+fn a_missing_file_gives_empty_tables() {
+    let dir = ScratchDir::new("highscore_missing");
+    let scores = HighScores::load(&dir.join("highscores.json"));
+    assert!(scores.table("40x20 Normal").is_empty());
+    assert!(!dir.join("highscores.json.bak").exists());
+}
+
+#[test]
+fn saved_tables_load_back() {
+    let dir = ScratchDir::new("highscore_round_trip");
+    let path = dir.join("nested").join("highscores.json");
+    let mut scores = HighScores::default();
+    scores.insert("40x20 Normal", entry("Ada", 12));
+    scores.insert("20x10 Hard Wrap", entry("Bo", 3));
+
+    scores.save(&path).unwrap();
+    let loaded = HighScores::load(&path);
+
+    assert_eq!(loaded.table("40x20 Normal"), [entry("Ada", 12)]);
+    assert_eq!(loaded.table("20x10 Hard Wrap"), [entry("Bo", 3)]);
+}
+
+#[test]
+fn tables_keep_the_best_scores_in_order() {
+    let mut scores = HighScores::default();
+    let key = "40x20 Normal";
+    assert!(!scores.qualifies(key, 0));
+    for score in 1..=TABLE_SIZE as i32 {
+        assert!(scores.qualifies(key, score));
+        assert_eq!(scores.insert(key, entry("p", score)), Some(0));
+    }
+
+    // A tie goes below the score it ties with
+    assert_eq!(scores.insert(key, entry("tie", 5)), Some(6));
+    assert_eq!(scores.table(key).len(), TABLE_SIZE);
+    assert_eq!(scores.table(key).last().unwrap().score, 2);
+
+    assert!(!scores.qualifies(key, 2));
+    assert_eq!(scores.insert(key, entry("late", 2)), None);
+    let ranked: Vec<i32> = scores.table(key).iter().map(|e| e.score).collect();
+    assert_eq!(ranked, [10, 9, 8, 7, 6, 5, 5, 4, 3, 2]);
+}
+
+#[test]
+fn tables_are_named_after_the_board_and_pace() {
+    assert_eq!(HighScores::key(&settings()), "40x20 Normal");
+    assert_eq!(
+        HighScores::key(&Settings {
+            speed: 150,
+            walls: WallMode::Wrap,
+            ..settings()
+        }),
+        "40x20 150ms Wrap"
+    );
+    assert_eq!(
+        HighScores::key(&Settings {
+            speed: Difficulty::Hard.speed(),
+            level: Some(Level::bundled("pillars").unwrap()),
+            ..settings()
+        }),
+        "Pillars Hard"
+    );
+    assert_eq!(
+        HighScores::key(&Settings {
+            profile: Some(Profile::find("arcade").unwrap()),
+            food_mix: FoodMix::MIXED,
+            ..settings()
+        }),
+        "40x20 Arcade Mixed"
+    );
+    assert_eq!(HighScores::campaign_key(&settings()), "Campaign Normal");
+}
@@ -0,0 +1,120 @@
+//! Quick key combos against the keyboard's turn queue.
+
+mod common;
+
+use rusty_snake::controller::{self, KeyboardController, SnakeController, MAX_QUEUED_TURNS};
+use rusty_snake::game::{Direction, GameState};
+
+/// A one-player game with the snake in the middle of a roomy board, heading Right.
+fn new_game() -> GameState {
+    let game = GameState::new(&common::settings(40, 20, 1));
+    assert_eq!(game.snakes[0].direction, Direction::Right);
+    game
+}
+
+/// Steps the game once with the keyboard and returns the snake's heading.
+fn tick(game: &mut GameState, keyboard: &mut KeyboardController) -> Direction {
+    let mut controllers: [&mut dyn SnakeController; 1] = [keyboard];
+    controller::step(game, &mut controllers);
+    assert!(!game.is_over());
+    game.snakes[0].direction
+}
+
+fn press(keyboard: &mut KeyboardController, directions: &[Direction]) {
+    for &direction in directions {
+        keyboard.press(direction);
+    }
+}
+
+#[test]
+fn quick_up_left_turns_on_consecutive_ticks() {
+    let mut game = new_game();
+    let mut keyboard = KeyboardController::new();
+    let start = game.snakes[0].head();
+
+    press(&mut keyboard, &[Direction::Up, Direction::Left]);
+    assert_eq!(tick(&mut game, &mut keyboard), Direction::Up);
+    assert_eq!(tick(&mut game, &mut keyboard), Direction::Left);
+    assert_eq!(tick(&mut game, &mut keyboard), Direction::Left);
+
+    // A tight U-turn: one row up, and back past the column it started in
+    let head = game.snakes[0].head();
+    assert_eq!((head.x, head.y), (start.x - 2, start.y - 1));
+}
+
+#[test]
+fn reversal_is_dropped() {
+    let mut game = new_game();
+    let mut keyboard = KeyboardController::new();
+
+    press(&mut keyboard, &[Direction::Left]);
+    assert_eq!(tick(&mut game, &mut keyboard), Direction::Right);
+}
+
+#[test]
+fn turn_then_reversal_of_the_turn_is_dropped() {
+    let mut game = new_game();
+    let mut keyboard = KeyboardController::new();
+
+    // Down would reverse the Up it follows, so it never applies
+    press(&mut keyboard, &[Direction::Up, Direction::Down]);
+    assert_eq!(tick(&mut game, &mut keyboard), Direction::Up);
+    assert_eq!(tick(&mut game, &mut keyboard), Direction::Up);
+}
+
+#[test]
+fn dropped_presses_do_not_cost_a_tick() {
+    let mut game = new_game();
+    let mut keyboard = KeyboardController::new();
+
+    // Left reverses and Right is the current heading; Up still turns at once
+    press(&mut keyboard, &[Direction::Left, Direction::Right, Direction::Up]);
+    assert_eq!(tick(&mut game, &mut keyboard), Direction::Up);
+}
+
+#[test]
+fn three_turn_combo_takes_a_tick_per_turn() {
+    let mut game = new_game();
+    let mut keyboard = KeyboardController::new();
+
+    // Down, Left, Up: down a row, back along it, then up again
+    press(&mut keyboard, &[Direction::Down, Direction::Left, Direction::Up]);
+    assert_eq!(tick(&mut game, &mut keyboard), Direction::Down);
+    assert_eq!(tick(&mut game, &mut keyboard), Direction::Left);
+    assert_eq!(tick(&mut game, &mut keyboard), Direction::Up);
+}
+
+#[test]
+fn repeated_presses_count_once() {
+    let mut game = new_game();
+    let mut keyboard = KeyboardController::new();
+
+    press(&mut keyboard, &[Direction::Up, Direction::Up, Direction::Up, Direction::Left]);
+    assert_eq!(tick(&mut game, &mut keyboard), Direction::Up);
+    assert_eq!(tick(&mut game, &mut keyboard), Direction::Left);
+}
+
+#[test]
+fn queue_is_bounded() {
+    let mut game = new_game();
+    let mut keyboard = KeyboardController::new();
+
+    let presses = [Direction::Up, Direction::Left, Direction::Down, Direction::Right];
+    assert_eq!(MAX_QUEUED_TURNS, presses.len() - 1);
+    press(&mut keyboard, &presses);
+    assert_eq!(tick(&mut game, &mut keyboard), Direction::Up);
+    assert_eq!(tick(&mut game, &mut keyboard), Direction::Left);
+    assert_eq!(tick(&mut game, &mut keyboard), Direction::Down);
+    // The last press didn't fit
+    assert_eq!(tick(&mut game, &mut keyboard), Direction::Down);
+}
+
+#[test]
+fn clear_forgets_queued_turns() {
+    let mut game = new_game();
+    let mut keyboard = KeyboardController::new();
+
+    press(&mut keyboard, &[Direction::Up, Direction::Left]);
+    keyboard.clear();
+    assert_eq!(tick(&mut game, &mut keyboard), Direction::Right);
+}
@@ -0,0 +1,159 @@
+//! Key bindings: presets, key names, reading `keys.json` and conflicts.
+
+mod common;
+
+use std::fs;
+
+use common::ScratchDir;
+use crossterm::event::KeyCode;
+use rusty_snake::game::Direction;
+use rusty_snake::keys::{key_name, parse_key, Action, KeyBindings, KeysError};
+
+/// Loads bindings from a file holding `contents`, starting from `preset`.
+fn load(contents: &str, preset: Option<&str>) -> Result<KeyBindings, KeysError> {
+    let dir = ScratchDir::new("keys");
+    let path = dir.join("keys.json");
+    fs::write(&path, contents).unwrap();
+    KeyBindings::load(&path, preset)
+}
+
+/// The message of a key file that could not be loaded.
+fn load_error(contents: &str, preset: Option<&str>) -> String {
+    load(contents, preset).unwrap_err().to_string()
+}
+
+#[test]
+fn a_missing_file_falls_back_to_the_preset() {
+    let dir = ScratchDir::new("keys_missing");
+    let path = dir.join("keys.json");
+    assert_eq!(KeyBindings::load(&path, None).unwrap(), KeyBindings::default());
+    assert_eq!(KeyBindings::load(&path, Some("vim")).unwrap(), KeyBindings::preset("vim").unwrap());
+}
+
+#[test]
+fn presets_steer_each_player() {
+    let arrows = KeyBindings::default();
+    assert_eq!(arrows.action(KeyCode::Up), Some(Action::Up));
+    assert_eq!(arrows.action(KeyCode::Char('W')), Some(Action::Up2));
+    assert_eq!(arrows.describe(Action::Faster), "+ or =");
+    assert_eq!(arrows.describe(Action::Quit), "q");
+
+    let vim = KeyBindings::preset("VIM").unwrap();
+    assert_eq!(vim.action(KeyCode::Char('h')).and_then(Action::turn), Some((0, Direction::Left)));
+    assert_eq!(vim.action(KeyCode::Char('a')).and_then(Action::turn), Some((1, Direction::Left)));
+    assert_eq!(vim.action(KeyCode::Left), None);
+
+    let wasd = KeyBindings::preset("wasd").unwrap();
+    assert_eq!(wasd.action(KeyCode::Char('s')), Some(Action::Down));
+    assert_eq!(wasd.action(KeyCode::Down), Some(Action::Down2));
+}
+
+#[test]
+fn key_names_read_back() {
+    for name in ["q", "Space", "Up", "Down", "Left", "Right", "Tab", "Enter", "Esc", "Backspace", "F1", "F12"] {
+        assert_eq!(key_name(parse_key(name).unwrap()), name);
+    }
+    assert_eq!(parse_key("Q"), Ok(KeyCode::Char('q')));
+    assert_eq!(parse_key("esc"), Ok(KeyCode::Esc));
+    assert_eq!(parse_key("F13"), Err("unknown key `F13`".to_string()));
+    assert_eq!(parse_key("F0"), Err("unknown key `F0`".to_string()));
+    assert_eq!(parse_key("Home"), Err("unknown key `Home`".to_string()));
+}
+
+#[test]
+fn bindings_replace_the_presets_keys() {
+    let keys = load(
+        r#"{
+            "preset": "vim",
+            "bindings": {
+                "quit": ["Q", "Esc"],
+                "pause": ["Space", "p"],
+                "help": ["F1"],
+                "autopilot": ["Enter"],
+                "restart": ["Backspace"]
+            }
+        }"#,
+        None,
+    )
+    .unwrap();
+    assert_eq!(keys.keys(Action::Quit), [KeyCode::Char('q'), KeyCode::Esc]);
+    assert_eq!(keys.describe(Action::Quit), "q or Esc");
+    assert_eq!(keys.action(KeyCode::Char('p')), Some(Action::Pause));
+    assert_eq!(keys.action(KeyCode::F(1)), Some(Action::Help));
+    assert_eq!(keys.action(KeyCode::Char('?')), None);
+    assert_eq!(keys.action(KeyCode::Char('k')), Some(Action::Up));
+
+    let unbound = load(r#"{ "bindings": { "help": [] } }"#, None).unwrap();
+    assert_eq!(unbound.describe(Action::Help), "(unbound)");
+}
+
+#[test]
+fn bad_names_are_reported() {
+    assert_eq!(load_error(r#"{ "bindings": { "quit": ["F13"] } }"#, None), "quit: unknown key `F13`");
+    assert_eq!(load_error(r#"{ "bindings": { "jump": ["j"] } }"#, None), "no action named `jump`");
+    assert_eq!(
+        load_error(r#"{ "preset": "emacs" }"#, None),
+        "no key preset named `emacs` (try arrows, wasd, vim)"
+    );
+    assert!(matches!(load(r#"{ "preset": "vim", "colors": {} }"#, None), Err(KeysError::Parse(_))));
+    assert!(matches!(load("{ \"bindings\": ", None), Err(KeysError::Parse(_))));
+}
+
+#[test]
+fn a_key_bound_twice_is_a_conflict() {
+    assert_eq!(
+        load_error(r#"{ "bindings": { "pause": ["r"] } }"#, None),
+        "`r` is bound to both pause and restart"
+    );
+    assert!(matches!(
+        load(r#"{ "bindings": { "pause": ["R"] } }"#, None),
+        Err(KeysError::Conflict { first: Action::Pause, second: Action::Restart, .. })
+    ));
+}
+
+#[test]
+fn player_two_keys_are_checked_for_conflicts() {
+    // With player 1's actions
+    assert_eq!(
+        load_error(r#"{ "bindings": { "p2_up": ["q"] } }"#, None),
+        "`q` is bound to both quit and p2_up"
+    );
+    // With player 1's moves
+    assert_eq!(
+        load_error(r#"{ "bindings": { "p2_left": ["Left"] } }"#, None),
+        "`Left` is bound to both left and p2_left"
+    );
+    // With each other
+    assert_eq!(
+        load_error(r#"{ "bindings": { "p2_up": ["s"] } }"#, None),
+        "`s` is bound to both p2_up and p2_down"
+    );
+}
+
+#[test]
+fn a_preset_given_overrides_the_files() {
+    // `h` is free in the arrows preset, but moves left in vim's
+    let keys = r#"{ "preset": "arrows", "bindings": { "help": ["h"] } }"#;
+    assert_eq!(load(keys, None).unwrap().action(KeyCode::Char('h')), Some(Action::Help));
+    assert_eq!(load_error(keys, Some("vim")), "`h` is bound to both left and help");
+    assert_eq!(
+        load_error(keys, Some("emacs")),
+        "no key preset named `emacs` (try arrows, wasd, vim)"
+    );
+}
+
+#[test]
+fn the_game_loads_the_bindings_it_is_given() {
+    let vim = ("keys.json", r#"{ "preset": "vim", "bindings": { "help": ["F1"] } }"#);
+    assert_eq!(common::run_error(&common::run_with_config(&[vim], &[])), None);
+    assert_eq!(common::run_error(&common::run_with_config(&[], &["--keys", "wasd"])), None);
+    assert_eq!(
+        common::run_error(&common::run_with_config(&[vim], &["--keys", "emacs"])).as_deref(),
+        Some("Could not load key bindings: no key preset named `emacs` (try arrows, wasd, vim)")
+    );
+    let clash = ("keys.json", r#"{ "preset": "arrows", "bindings": { "help": ["h"] } }"#);
+    assert_eq!(
+        common::run_error(&common::run_with_config(&[clash], &["--keys", "vim"])).as_deref(),
+        Some("Could not load key bindings: `h` is bound to both left and help")
+    );
+}
@@ -0,0 +1,178 @@
+//! Reading level maps.
+
+mod common;
+
+use rusty_snake::game::{Direction, GameState, Point, MAX_BOARD_SIZE, MIN_BOARD_SIZE};
+use rusty_snake::level::{Level, LevelError};
+
+/// A `width` x `height` map: the border, the start in the top-left corner and
+/// floor everywhere else.
+fn open_map(width: usize, height: usize) -> String {
+    let wall = "#".repeat(width);
+    let mut rows = vec![wall.clone()];
+    for y in 1..height - 1 {
+        let start = if y == 1 { "S" } else { "." };
+        rows.push(format!("#{}{}#", start, ".".repeat(width - 3)));
+    }
+    rows.push(wall);
+    rows.join("\n")
+}
+
+/// The message of a parse error.
+fn parse_error(map: &str) -> String {
+    parse_error_at(map).1
+}
+
+/// The line and message of a parse error.
+fn parse_error_at(map: &str) -> (usize, String) {
+    match map.parse::<Level>() {
+        Err(LevelError::Parse { line, message }) => (line, message),
+        other => panic!("expected a parse error, got {:?}", other),
+    }
+}
+
+/// A 10x8 map with two obstacles.
+const PILLARS: &str = "\
+name: Small Pillars
+direction: up
+target: 7
+
+##########
+#........#
+#.#....#.#
+#........#
+#...S....#
+#........#
+#........#
+##########
+";
+
+#[test]
+fn a_map_is_read() {
+    let level: Level = PILLARS.parse().unwrap();
+    assert_eq!(level.name, "Small Pillars");
+    assert_eq!((level.width, level.height), (10, 8));
+    assert_eq!(level.start, Point { x: 4, y: 4 });
+    assert_eq!(level.direction, Direction::Up);
+    assert_eq!(level.target, Some(7));
+    let mut obstacles: Vec<Point> = level.obstacles.iter().copied().collect();
+    obstacles.sort_by_key(|p| (p.y, p.x));
+    assert_eq!(obstacles, [Point { x: 2, y: 2 }, Point { x: 7, y: 2 }]);
+    assert!(level.is_wall(Point { x: 0, y: 3 }));
+    assert!(level.is_wall(Point { x: 7, y: 2 }));
+    assert!(!level.is_wall(Point { x: 4, y: 4 }));
+}
+
+#[test]
+fn metadata_is_optional() {
+    let map = PILLARS.lines().skip(4).collect::<Vec<_>>().join("\n");
+    let level: Level = map.parse().unwrap();
+    assert_eq!(level.name, "Untitled");
+    assert_eq!(level.direction, Direction::Right);
+    assert_eq!(level.target, None);
+}
+
+#[test]
+fn a_level_writes_back_out_as_the_same_map() {
+    let level: Level = PILLARS.parse().unwrap();
+    let reread: Level = level.to_string().parse().unwrap();
+    assert_eq!(reread, level);
+}
+
+#[test]
+fn bundled_levels_are_valid() {
+    let names: Vec<&str> = Level::bundled_names().collect();
+    assert_eq!(names, ["pillars", "crossroads", "tunnels", "rooms", "spiral"]);
+    for name in names {
+        let level = Level::bundled(name).unwrap();
+        assert!(!level.is_wall(level.start), "{}", name);
+        assert!(level.target.is_some(), "{}", name);
+    }
+    assert_eq!(Level::bundled("PILLARS").unwrap().name, "Pillars");
+    assert!(matches!(Level::bundled("maze"), Err(LevelError::Unknown(_))));
+    assert!(matches!(Level::find("no/such/level.txt"), Err(LevelError::Io(_))));
+}
+
+#[test]
+fn mistakes_are_reported_with_their_line() {
+    let with = |from: &str, to: &str| PILLARS.replacen(from, to, 1);
+    assert_eq!(
+        parse_error_at(&with("direction: up", "direction: sideways")),
+        (2, "unknown direction `sideways`".to_string())
+    );
+    assert_eq!(
+        parse_error_at(&with("target: 7", "target: lots")),
+        (3, "invalid target `lots`".to_string())
+    );
+    assert_eq!(
+        parse_error_at(&with("target: 7", "speed: 7")),
+        (3, "unknown metadata `speed`".to_string())
+    );
+    assert_eq!(
+        parse_error_at(&with("#.#....#.#", "#.#....#.")),
+        (7, "all map rows must be the same width".to_string())
+    );
+    assert_eq!(
+        parse_error_at(&with("#........#", ".........#")),
+        (6, "the border must be all walls".to_string())
+    );
+    assert_eq!(
+        parse_error_at(&with("#.#....#.#", "#.#..S.#.#")),
+        (9, "the map has more than one `S`".to_string())
+    );
+    assert_eq!(
+        parse_error_at(&with("#.#....#.#", "#.#..x.#.#")),
+        (7, "unexpected character `x`".to_string())
+    );
+    assert_eq!(
+        parse_error_at(&with("#...S....#", "#........#")),
+        (12, "the map has no `S` start".to_string())
+    );
+}
+
+#[test]
+fn text_after_the_map_is_refused() {
+    // A stray blank line would otherwise crop the map to the rows above it
+    let cropped = PILLARS.replacen("#........#\n#........#\n##########", "#........#\n\n#........#\n##########", 1);
+    assert_eq!(parse_error_at(&cropped), (12, "unexpected text after the map".to_string()));
+
+    // Trailing blank lines are fine
+    let padded = format!("{}\n\n  \n", PILLARS);
+    assert_eq!(padded.parse::<Level>().unwrap(), PILLARS.parse::<Level>().unwrap());
+}
+
+#[test]
+fn the_smallest_and_largest_boards_are_allowed() {
+    for size in [MIN_BOARD_SIZE, MAX_BOARD_SIZE] {
+        let level: Level = open_map(size as usize, size as usize).parse().unwrap();
+        assert_eq!((level.width, level.height), (size, size));
+    }
+}
+
+#[test]
+fn maps_outside_the_board_size_limits_are_refused() {
+    let small = (MIN_BOARD_SIZE - 1) as usize;
+    let large = (MAX_BOARD_SIZE + 1) as usize;
+    for (width, height) in [(3, 3), (small, 10), (10, small), (large, 10), (10, large)] {
+        let message = parse_error(&open_map(width, height));
+        assert!(
+            message.starts_with(&format!("a {}x{} board is not allowed", width, height)),
+            "{}",
+            message
+        );
+    }
+}
+
+#[test]
+fn a_map_needs_floor_for_the_food() {
+    // Walls everywhere but the start
+    let mut rows = vec!["##########".to_string(); 10];
+    rows[1] = "#S########".to_string();
+    assert_eq!(parse_error(&rows.join("\n")), "the map has no floor left for the food");
+
+    // One floor cell is enough, and the first food goes there
+    rows[2] = "#.########".to_string();
+    let level: Level = rows.join("\n").parse().unwrap();
+    let game = GameState::new(&common::level_settings(level, 0));
+    assert_eq!((game.food.x, game.food.y), (1, 2));
+}
@@ -0,0 +1,143 @@
+//! A host and a client on localhost: the handshake, and games that stay in
+//! lockstep.
+
+mod common;
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rusty_snake::controller::{self, KeyboardController};
+use rusty_snake::game::{Direction, FoodMix, GameState, Settings, WallMode};
+use rusty_snake::net::{self, Client, Host, NetError, Update, DEFAULT_PORT, VERSION};
+
+fn settings() -> Settings {
+    Settings {
+        walls: WallMode::Wrap,
+        players: 2,
+        food_mix: FoodMix::MIXED,
+        ..common::settings(30, 16, 9)
+    }
+}
+
+/// Polls the host until the client has been welcomed.
+fn welcome(host: &mut Host) {
+    let start = Instant::now();
+    while !host.is_ready() {
+        host.poll().unwrap();
+        assert!(start.elapsed() < Duration::from_secs(5), "no hello from the client");
+        thread::sleep(Duration::from_millis(5));
+    }
+}
+
+#[test]
+fn host_and_client_end_in_the_same_state() {
+    const TICKS: u64 = 40;
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+
+    let client = thread::spawn(move || {
+        let mut client = Client::join(&addr).unwrap();
+        assert_eq!(client.player, 1);
+        assert_eq!(client.settings, settings());
+        let mut game = GameState::new(&client.settings);
+        // Turn now and then; the host decides which tick each turn lands on
+        let turns = [Direction::Up, Direction::Left, Direction::Down, Direction::Right];
+        while game.tick < TICKS && !game.is_over() {
+            match client.poll(&mut game).unwrap() {
+                Some(Update::Stepped(_)) if game.tick.is_multiple_of(5) => {
+                    client.turn(turns[(game.tick / 5) as usize % turns.len()]).unwrap();
+                }
+                Some(_) => {}
+                None => thread::sleep(Duration::from_millis(1)),
+            }
+        }
+        client.quit();
+        game
+    });
+
+    let (stream, _) = listener.accept().unwrap();
+    let mut host = Host::accept(stream, &settings()).unwrap();
+    welcome(&mut host);
+    assert_eq!(host.player(), 1);
+
+    let mut game = GameState::new(&settings());
+    let mut keyboard = KeyboardController::new();
+    while game.tick < TICKS && !game.is_over() {
+        host.poll().unwrap();
+        if game.tick == 3 {
+            keyboard.press(Direction::Down);
+        }
+        controller::step(&mut game, &mut [&mut keyboard, &mut host]);
+        host.send_tick(&game).unwrap();
+        thread::sleep(Duration::from_millis(5));
+    }
+
+    let joined = client.join().unwrap();
+    assert_eq!(joined.tick, game.tick);
+    assert_eq!(joined.food, game.food);
+    assert_eq!(joined.food_kind, game.food_kind);
+    assert_eq!(joined.status, game.status);
+    for (theirs, ours) in joined.snakes.iter().zip(&game.snakes) {
+        assert_eq!(theirs.body, ours.body);
+        assert_eq!(theirs.score, ours.score);
+        assert_eq!(theirs.death, ours.death);
+    }
+}
+
+#[test]
+fn accepting_does_not_wait_for_the_hello() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    // Connects, but says nothing yet
+    let mut silent = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (stream, _) = listener.accept().unwrap();
+
+    let start = Instant::now();
+    let mut host = Host::accept(stream, &settings()).unwrap();
+    host.poll().unwrap();
+    assert!(!host.is_ready());
+    assert!(start.elapsed() < Duration::from_secs(1));
+
+    writeln!(silent, "hello {}", VERSION).unwrap();
+    welcome(&mut host);
+    let mut line = String::new();
+    BufReader::new(silent).read_line(&mut line).unwrap();
+    assert!(line.starts_with("welcome 2 "), "{}", line);
+}
+
+#[test]
+fn another_version_is_refused() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let mut old = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    writeln!(old, "hello {}", VERSION - 1).unwrap();
+    let (stream, _) = listener.accept().unwrap();
+
+    let mut host = Host::accept(stream, &settings()).unwrap();
+    let start = Instant::now();
+    let err = loop {
+        match host.poll() {
+            Ok(()) => assert!(start.elapsed() < Duration::from_secs(5), "no hello from the client"),
+            Err(err) => break err,
+        }
+        thread::sleep(Duration::from_millis(5));
+    };
+    assert!(matches!(err, NetError::Protocol(_)), "{}", err);
+    assert!(!host.is_ready());
+
+    let mut line = String::new();
+    BufReader::new(old).read_line(&mut line).unwrap();
+    assert!(line.starts_with("refused version"), "{}", line);
+}
+
+#[test]
+fn addresses_without_a_port_get_the_default_one() {
+    let resolve = |addr: &str| net::resolve(addr).unwrap().to_string();
+    assert_eq!(resolve("127.0.0.1"), format!("127.0.0.1:{}", DEFAULT_PORT));
+    assert_eq!(resolve("127.0.0.1:9000"), "127.0.0.1:9000");
+    assert_eq!(resolve("::1"), format!("[::1]:{}", DEFAULT_PORT));
+    assert_eq!(resolve("fe80::1"), format!("[fe80::1]:{}", DEFAULT_PORT));
+    assert_eq!(resolve("[::1]:9000"), "[::1]:9000");
+    assert_eq!(net::resolve("localhost").unwrap().port(), DEFAULT_PORT);
+    assert_eq!(net::resolve("localhost:9000").unwrap().port(), 9000);
+}
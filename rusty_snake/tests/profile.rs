@@ -0,0 +1,126 @@
+//! Difficulty profiles: their speed curves, and levels in a game.
+
+mod common;
+
+use rusty_snake::game::{speed_limits, Direction, FoodKind, FoodMix, GameState, Point, Settings};
+use rusty_snake::profile::{Curve, Profile};
+
+fn built_in(name: &str) -> Profile {
+    Profile::find(name).unwrap()
+}
+
+#[test]
+fn steps_get_one_step_faster_each_level() {
+    let arcade = built_in("arcade");
+    let ticks: Vec<u64> = [0, 4, 5, 9, 10, 15, 20, 1000].iter().map(|&score| arcade.tick(score)).collect();
+    assert_eq!(ticks, [200, 200, 150, 150, 100, 50, 50, 50]);
+}
+
+#[test]
+fn smooth_halves_the_gap_each_level() {
+    let marathon = built_in("marathon");
+    assert_eq!(marathon.tick(0), 300);
+    assert_eq!(marathon.tick(5), 240);
+    assert_eq!(marathon.tick(10), 180);
+    assert_eq!(marathon.tick(20), 120);
+    assert_eq!(marathon.tick(10_000), 60);
+}
+
+#[test]
+fn curves_never_slow_down_or_pass_the_fastest_tick() {
+    let mut profiles = Profile::built_in();
+    profiles.push(Profile {
+        name: "odd".to_string(),
+        start: 170,
+        min: 300,
+        curve: Curve::Smooth,
+        foods: 0,
+    });
+    for profile in profiles {
+        let fastest = profile.min.min(profile.start);
+        let mut last = profile.tick(0);
+        assert_eq!(last, profile.start, "{}", profile.name);
+        for score in 1..500 {
+            let tick = profile.tick(score);
+            assert!(tick <= last && tick >= fastest, "{} at {}", profile.name, score);
+            last = tick;
+        }
+    }
+}
+
+#[test]
+fn levels_count_from_one() {
+    let sprint = built_in("sprint");
+    let levels: Vec<u32> = [-2, 0, 2, 3, 6, 7].iter().map(|&score| sprint.level(score)).collect();
+    assert_eq!(levels, [1, 1, 1, 2, 3, 3]);
+}
+
+#[test]
+fn profiles_are_found_by_name() {
+    assert_eq!(built_in("MARATHON").name, "marathon");
+    assert_eq!(built_in("sprint").to_string(), "Sprint");
+    assert_eq!(
+        Profile::find("zen").unwrap_err(),
+        "no profile named `zen` (try arcade, marathon, sprint)"
+    );
+}
+
+/// A game on the arcade profile where player 1 has `score` points and the
+/// food, of `kind`, is right in front of the snake.
+fn about_to_eat(score: i32, kind: FoodKind) -> GameState {
+    let mut game = GameState::new(&Settings {
+        speed: 200,
+        profile: Some(built_in("arcade")),
+        food_mix: FoodMix::MIXED,
+        ..common::settings(40, 20, 1)
+    });
+    game.snakes[0].score = score;
+    game.peak_score = score;
+    game.snakes[0].direction = Direction::Right;
+    game.snakes[0].next_direction = Direction::Right;
+    set_food(&mut game, kind);
+    game
+}
+
+fn set_food(game: &mut GameState, kind: FoodKind) {
+    let head = game.snakes[0].head();
+    game.food = Point { x: head.x + 1, y: head.y };
+    game.food_kind = kind;
+    game.food_expires = None;
+}
+
+#[test]
+fn reaching_a_level_speeds_the_game_up() {
+    let mut game = about_to_eat(4, FoodKind::Regular);
+    assert_eq!(game.level(), Some(1));
+
+    game.step(&[None]);
+
+    let (fastest, slowest) = speed_limits(40, 20);
+    assert_eq!(game.level(), Some(2));
+    assert_eq!(game.peak_score, 5);
+    assert_eq!(game.speed, 150u64.clamp(fastest, slowest));
+}
+
+#[test]
+fn poison_does_not_take_a_level_back() {
+    let mut game = about_to_eat(4, FoodKind::Regular);
+    game.step(&[None]);
+    let speed = game.speed;
+
+    set_food(&mut game, FoodKind::Poison);
+    game.step(&[None]);
+    assert_eq!(game.snakes[0].score, 4);
+    assert_eq!(game.level(), Some(2));
+    assert_eq!(game.peak_score, 5);
+    assert_eq!(game.speed, speed);
+
+    // Winning the point back is not a new level, so a manual speed change stays
+    game.slower();
+    let slowed = game.speed;
+    set_food(&mut game, FoodKind::Regular);
+    game.step(&[None]);
+    assert_eq!(game.snakes[0].score, 5);
+    assert_eq!(game.level(), Some(2));
+    assert_eq!(game.speed, slowed);
+}
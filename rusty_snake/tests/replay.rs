@@ -0,0 +1,210 @@
+//! Recording games, and reading and playing back replay files.
+
+mod common;
+
+use rusty_snake::autopilot;
+use rusty_snake::game::{Direction, FoodMix, GameState, Settings, WallMode, MAX_BOARD_SIZE, MIN_BOARD_SIZE};
+use rusty_snake::level::Level;
+use rusty_snake::profile::Profile;
+use rusty_snake::replay::{Player, Recorder, Replay, ReplayError, VERSION};
+
+/// A replay file with the given lines after the header.
+fn replay_file(lines: &[&str]) -> String {
+    let mut text = format!("rusty_snake replay {}\n", VERSION);
+    for line in lines {
+        text.push_str(line);
+        text.push('\n');
+    }
+    text
+}
+
+/// The message of a parse error.
+fn parse_error(text: &str) -> String {
+    match text.parse::<Replay>() {
+        Err(ReplayError::Parse { message, .. }) => message,
+        other => panic!("expected a parse error, got {:?}", other),
+    }
+}
+
+/// An 8x8 level map, as the lines of a `level` section.
+const BOX: [&str; 9] = [
+    "name: Box",
+    "########",
+    "#S.....#",
+    "#......#",
+    "#..##..#",
+    "#..##..#",
+    "#......#",
+    "#......#",
+    "########",
+];
+
+#[test]
+fn boards_outside_the_size_limits_are_refused() {
+    let small = MIN_BOARD_SIZE - 1;
+    let large = MAX_BOARD_SIZE + 1;
+    for (width, height) in [(3, 3), (small, 20), (20, small), (large, 20), (20, large), (70_000, 20)] {
+        let board = format!("board {} {}", width, height);
+        let message = parse_error(&replay_file(&[&board, "speed 200", "seed 1"]));
+        assert!(
+            message.starts_with(&format!("a {}x{} board is not allowed", width, height)),
+            "{}",
+            message
+        );
+    }
+}
+
+#[test]
+fn boards_within_the_size_limits_are_read() {
+    for (width, height) in [(MIN_BOARD_SIZE, MIN_BOARD_SIZE), (MAX_BOARD_SIZE, MAX_BOARD_SIZE)] {
+        let board = format!("board {} {}", width, height);
+        let replay: Replay = replay_file(&[&board, "speed 200", "seed 1"]).parse().unwrap();
+        assert_eq!((replay.settings.width, replay.settings.height), (width, height));
+    }
+}
+
+#[test]
+fn a_level_must_match_the_board() {
+    let mut lines = vec!["board 8 8", "speed 200", "seed 1", "level 9"];
+    lines.extend(BOX);
+    let replay: Replay = replay_file(&lines).parse().unwrap();
+    assert_eq!(replay.settings.level.unwrap().name, "Box");
+
+    lines[0] = "board 40 20";
+    assert_eq!(parse_error(&replay_file(&lines)), "the board is 40x20, but the level is 8x8");
+}
+
+#[test]
+fn a_level_outside_the_size_limits_is_refused() {
+    let lines = [
+        "board 8 8",
+        "speed 200",
+        "seed 1",
+        "level 4",
+        "name: Tiny",
+        "######",
+        "#S...#",
+        "######",
+    ];
+    let message = parse_error(&replay_file(&lines));
+    assert!(message.starts_with("invalid level: line 4: a 6x3 board is not allowed"), "{}", message);
+}
+
+#[test]
+fn the_profile_and_food_mix_are_recorded() {
+    let replay = Replay {
+        settings: Settings {
+            speed: 300,
+            profile: Some(Profile::find("marathon").unwrap()),
+            food_mix: FoodMix::MIXED,
+            ..common::settings(30, 15, 5)
+        },
+        events: Vec::new(),
+        end: None,
+    };
+    let text = replay.to_string();
+    assert_eq!(VERSION, 4);
+    assert!(text.starts_with("rusty_snake replay 4\n"), "{}", text);
+    assert!(text.contains("\nprofile marathon 60 smooth 10\n"), "{}", text);
+    assert!(text.contains("\nfood 12 3 2 3\n"), "{}", text);
+    assert_eq!(text.parse::<Replay>().unwrap(), replay);
+
+    // Defaults are left out
+    let classic = Replay {
+        settings: Settings {
+            profile: None,
+            food_mix: FoodMix::CLASSIC,
+            ..replay.settings.clone()
+        },
+        ..replay
+    };
+    let text = classic.to_string();
+    assert!(!text.contains("profile") && !text.contains("food"), "{}", text);
+    assert_eq!(text.parse::<Replay>().unwrap(), classic);
+}
+
+#[test]
+fn older_versions_are_refused() {
+    let text = replay_file(&["board 20 10", "speed 200", "seed 1"]).replace("replay 4", "replay 3");
+    assert!(matches!(text.parse::<Replay>(), Err(ReplayError::UnsupportedVersion(_))));
+}
+
+/// Plays `settings` for up to `ticks` steps with the autopilot steering
+/// everyone, and a few speed changes along the way, recording as it goes.
+fn record(settings: &Settings, ticks: u64) -> (GameState, Replay) {
+    let mut game = GameState::new(settings);
+    let mut recorder = Recorder::new(settings, &game);
+    while game.tick < ticks && !game.is_over() {
+        let turns: Vec<Option<Direction>> = (0..game.snakes.len())
+            .map(|player| autopilot::choose_direction(&game, player))
+            .collect();
+        game.step(&turns);
+        recorder.observe(&game);
+        match game.tick % 40 {
+            10 => game.faster(),
+            30 => game.slower(),
+            _ => continue,
+        }
+        recorder.observe(&game);
+    }
+    let replay = recorder.finish(&game);
+    (game, replay)
+}
+
+/// Reads `replay` back from its text and plays it to its end.
+fn play_back(replay: &Replay) -> GameState {
+    let parsed: Replay = replay.to_string().parse().unwrap();
+    assert_eq!(&parsed, replay);
+    let (end_tick, _) = parsed.end.clone().unwrap();
+    let mut game = parsed.new_game();
+    let mut player = Player::new(&parsed);
+    while game.tick < end_tick {
+        player.step(&mut game);
+    }
+    game
+}
+
+fn assert_same_game(played: &GameState, replayed: &GameState) {
+    assert_eq!(played.tick, replayed.tick);
+    assert_eq!(played.speed, replayed.speed);
+    assert_eq!(played.status, replayed.status);
+    assert_eq!(played.food, replayed.food);
+    assert_eq!(played.food_kind, replayed.food_kind);
+    for (a, b) in played.snakes.iter().zip(&replayed.snakes) {
+        assert_eq!(a.body, b.body);
+        assert_eq!(a.score, b.score);
+        assert_eq!(a.death, b.death);
+    }
+}
+
+#[test]
+fn a_two_player_game_plays_back_the_same() {
+    let settings = Settings {
+        speed: 150,
+        walls: WallMode::Wrap,
+        players: 2,
+        profile: Some(Profile::find("sprint").unwrap()),
+        food_mix: FoodMix::MIXED,
+        ..common::settings(30, 15, 11)
+    };
+    let (played, replay) = record(&settings, 300);
+    assert!(replay.events.len() > 10, "{} events", replay.events.len());
+    assert!(played.best_score() > 0);
+
+    let replayed = play_back(&replay);
+    assert_same_game(&played, &replayed);
+    let scores: Vec<i32> = replayed.snakes.iter().map(|snake| snake.score).collect();
+    assert_eq!(replay.end, Some((replayed.tick, scores)));
+}
+
+#[test]
+fn a_level_game_plays_back_the_same() {
+    let level = Level::bundled("rooms").unwrap();
+    let settings = Settings {
+        speed: 100,
+        ..common::level_settings(level, 3)
+    };
+    let (played, replay) = record(&settings, 300);
+    assert!(played.best_score() > 0);
+    assert_same_game(&played, &play_back(&replay));
+}
@@ -0,0 +1,165 @@
+//! Themes: the built-in ones, reading `themes.json`, and picking one.
+
+mod common;
+
+use std::fs;
+
+use common::ScratchDir;
+use crossterm::style::Color;
+use rusty_snake::game::FoodKind;
+use rusty_snake::theme::{Glyphs, Theme, ThemeError, Themes};
+
+const BUILT_IN: [&str; 5] = ["classic", "high-contrast", "mono", "colorblind", "okabe-ito"];
+
+/// Loads themes from a file holding `contents`.
+fn load(contents: &str) -> Result<Themes, ThemeError> {
+    let dir = ScratchDir::new("theme");
+    let path = dir.join("themes.json");
+    fs::write(&path, contents).unwrap();
+    Themes::load(&path)
+}
+
+/// The message of a theme file that could not be loaded.
+fn load_error(contents: &str) -> String {
+    load(contents).unwrap_err().to_string()
+}
+
+fn built_in(name: &str) -> Theme {
+    Themes::default().find(Some(name)).unwrap()
+}
+
+#[test]
+fn a_missing_file_gives_the_built_in_themes() {
+    let dir = ScratchDir::new("theme_missing");
+    let themes = Themes::load(&dir.join("themes.json")).unwrap();
+    assert_eq!(themes.names(), BUILT_IN);
+    assert_eq!(themes.find(None).unwrap(), Theme::default());
+    assert_eq!(Theme::default().name, "classic");
+    assert_eq!(themes.find(Some("Mono")).unwrap().name, "mono");
+}
+
+#[test]
+fn high_contrast_uses_bright_colors_only() {
+    let theme = built_in("high-contrast");
+    let mut colors = vec![theme.food, theme.bonus, theme.golden, theme.poison, theme.walls];
+    colors.extend(theme.snakes.iter().flat_map(|&(head, body)| [head, body]));
+    for color in colors {
+        assert!(!format!("{:?}", color).starts_with("Dark"), "{:?}", color);
+    }
+}
+
+#[test]
+fn food_is_drawn_by_kind() {
+    let theme = built_in("classic");
+    assert_eq!(theme.food(FoodKind::Regular), (Glyphs::BLOCKS.food, Color::Red));
+    assert_eq!(theme.food(FoodKind::Golden), (Glyphs::BLOCKS.golden, theme.golden));
+    assert_eq!(theme.food(FoodKind::Poison), (Glyphs::BLOCKS.poison, theme.poison));
+    assert_eq!(theme.snake_colors(2), theme.snake_colors(0));
+}
+
+#[test]
+fn ascii_swaps_only_the_unicode_glyphs() {
+    let glyphs = Glyphs {
+        head: '@',
+        ..Glyphs::BLOCKS
+    };
+    assert_eq!(glyphs.to_ascii(), Glyphs::ASCII);
+    let mono = built_in("mono").into_ascii();
+    assert_eq!(mono.glyphs.body, Glyphs::ASCII.body);
+    assert_eq!(mono.glyphs.wall, '#');
+}
+
+#[test]
+fn a_user_theme_overrides_its_base() {
+    let themes = load(
+        r##"{
+            "default": "dusk",
+            "themes": {
+                "dusk": {
+                    "base": "mono",
+                    "players": [{ "head": "#ffb000", "body": "dark_yellow" }, { "head": "208" }],
+                    "food": "magenta",
+                    "poison": " green ",
+                    "glyphs": { "head": "@", "food": "*", "golden": "$" }
+                }
+            }
+        }"##,
+    )
+    .unwrap();
+    assert_eq!(themes.names().last().map(String::as_str), Some("dusk"));
+
+    let dusk = themes.find(None).unwrap();
+    let mono = built_in("mono");
+    assert_eq!(dusk.name, "dusk");
+    assert_eq!(dusk.snakes[0], (Color::Rgb { r: 255, g: 176, b: 0 }, Color::DarkYellow));
+    assert_eq!(dusk.snakes[1], (Color::AnsiValue(208), mono.snakes[1].1));
+    assert_eq!(dusk.food, Color::Magenta);
+    assert_eq!(dusk.poison, Color::Green);
+    assert_eq!(dusk.bonus, mono.bonus);
+    assert_eq!(dusk.walls, mono.walls);
+    assert_eq!((dusk.glyphs.head, dusk.glyphs.food, dusk.glyphs.golden), ('@', '*', '$'));
+    assert_eq!(dusk.glyphs.body, mono.glyphs.body);
+    assert_eq!(themes.find(Some("DUSK")).unwrap(), dusk);
+}
+
+#[test]
+fn a_user_theme_can_replace_a_built_in_one() {
+    let themes = load(r#"{ "themes": { "Classic": { "walls": "grey" } } }"#).unwrap();
+    assert_eq!(themes.names().iter().filter(|name| name.eq_ignore_ascii_case("classic")).count(), 1);
+    let classic = themes.find(Some("classic")).unwrap();
+    assert_eq!(classic.walls, Color::Grey);
+    assert_eq!(classic.food, Theme::default().food);
+}
+
+#[test]
+fn an_unknown_theme_lists_the_choices() {
+    let themes = load(r#"{ "default": "dawn", "themes": { "dusk": {} } }"#).unwrap();
+    assert_eq!(
+        themes.find(None).unwrap_err().to_string(),
+        "no theme named `dawn` (try classic, high-contrast, mono, colorblind, okabe-ito, dusk)"
+    );
+    assert!(matches!(themes.find(Some("neon")), Err(ThemeError::Unknown { name, .. }) if name == "neon"));
+    assert_eq!(themes.find(Some("dusk")).unwrap().name, "dusk");
+}
+
+#[test]
+fn bad_colors_are_reported_with_their_theme() {
+    for color in ["beige", "#fff", "#gg0000", "256"] {
+        let contents = format!(r#"{{ "themes": {{ "dusk": {{ "walls": "{}" }} }} }}"#, color);
+        assert_eq!(load_error(&contents), format!("theme `dusk`: unknown color `{}`", color));
+    }
+}
+
+#[test]
+fn a_base_must_be_built_in() {
+    let contents = r#"{ "themes": { "dusk": { "walls": "grey" }, "night": { "base": "dusk" } } }"#;
+    assert_eq!(
+        load_error(contents),
+        "no theme named `dusk` (try classic, high-contrast, mono, colorblind, okabe-ito)"
+    );
+}
+
+#[test]
+fn mistakes_in_the_file_are_reported() {
+    assert!(matches!(load("{ \"themes\": "), Err(ThemeError::Parse(_))));
+    let message = load_error(r#"{ "themes": { "dusk": { "colour": "red" } } }"#);
+    assert!(message.contains("unknown field `colour`"), "{}", message);
+    let message = load_error(r#"{ "themes": { "dusk": { "glyphs": { "head": "@@" } } } }"#);
+    assert!(message.contains("expected a character"), "{}", message);
+}
+
+#[test]
+fn the_game_picks_the_theme_it_is_given() {
+    let themes = ("themes.json", r#"{ "themes": { "dusk": { "food": "magenta" } } }"#);
+    assert_eq!(common::run_error(&common::run_with_config(&[themes], &["--theme", "dusk"])), None);
+    assert_eq!(common::run_error(&common::run_with_config(&[], &["--theme", "mono", "--ascii"])), None);
+    assert_eq!(
+        common::run_error(&common::run_with_config(&[themes], &["--theme", "neon"])).as_deref(),
+        Some("no theme named `neon` (try classic, high-contrast, mono, colorblind, okabe-ito, dusk)")
+    );
+    let broken = ("themes.json", r#"{ "themes": { "dusk": { "food": "beige" } } }"#);
+    assert_eq!(
+        common::run_error(&common::run_with_config(&[broken], &[])).as_deref(),
+        Some("Could not load themes: theme `dusk`: unknown color `beige`")
+    );
+}
@@ -0,0 +1,147 @@
+//! Headless tournaments: playing the games, the statistics and the reports.
+
+mod common;
+
+use std::time::Duration;
+
+use rusty_snake::bot::{BotError, DEFAULT_TIMEOUT};
+use rusty_snake::controller::AutopilotController;
+use rusty_snake::game::{DeathCause, Settings};
+use rusty_snake::tournament::{self, report, Ending, Entrant, Format, GameRecord, Stats, Summary, AUTOPILOT};
+
+fn settings() -> Settings {
+    common::settings(20, 10, 40)
+}
+
+fn record(seed: u64, score: i32, ticks: u64, ending: Ending) -> GameRecord {
+    GameRecord {
+        seed,
+        score,
+        length: score as usize + 1,
+        ticks,
+        ending,
+    }
+}
+
+/// Four games: a median between two scores, a fractional mean, and two endings.
+fn summary(bot: &str) -> Summary {
+    Summary::new(
+        bot,
+        &[
+            record(1, 4, 100, Ending::Died(DeathCause::Wall)),
+            record(2, 1, 30, Ending::Died(DeathCause::Wall)),
+            record(3, 9, 500, Ending::OutOfTime),
+            record(4, 2, 61, Ending::Died(DeathCause::SelfCollision)),
+        ],
+    )
+}
+
+#[test]
+fn a_game_stops_at_the_tick_limit() {
+    let record = tournament::play(&settings(), &mut AutopilotController, 25);
+    assert_eq!(record.seed, 40);
+    assert_eq!(record.ticks, 25);
+    assert_eq!(record.ending, Ending::OutOfTime);
+    // The snake starts one cell long and grows a cell per regular food
+    assert_eq!(record.length, record.score as usize + 1);
+}
+
+#[test]
+fn every_entrant_plays_the_same_seeds_in_order() {
+    let entrants = [Entrant::new(AUTOPILOT, DEFAULT_TIMEOUT), Entrant::Autopilot];
+    let results = tournament::run(&settings(), &entrants, 5, 200, 3).unwrap();
+    assert_eq!(results.len(), 2);
+    let seeds: Vec<u64> = results[0].iter().map(|record| record.seed).collect();
+    assert_eq!(seeds, [40, 41, 42, 43, 44]);
+    // The autopilot is deterministic, so the thread count doesn't matter
+    assert_eq!(results[0], results[1]);
+    assert_eq!(tournament::run(&settings(), &entrants[..1], 5, 200, 1).unwrap()[0], results[0]);
+}
+
+#[test]
+fn a_bot_that_cannot_start_stops_the_tournament() {
+    let entrants = [Entrant::new("rusty_snake_no_such_bot", Duration::from_millis(50))];
+    assert_eq!(entrants[0].name(), "rusty_snake_no_such_bot");
+    let result = tournament::run(&settings(), &entrants, 3, 20, 2);
+    assert!(matches!(result, Err(BotError::Spawn(_))));
+}
+
+#[test]
+fn summaries_take_the_mean_median_and_max() {
+    let summary = summary("greedy");
+    assert_eq!(summary.games, 4);
+    assert_eq!(
+        summary.score,
+        Stats {
+            mean: 4.0,
+            median: 3.0,
+            max: 9.0
+        }
+    );
+    assert_eq!(
+        summary.ticks,
+        Stats {
+            mean: 172.75,
+            median: 80.5,
+            max: 500.0
+        }
+    );
+    let endings: Vec<(&str, usize)> = summary.endings.into_iter().collect();
+    assert_eq!(endings, [("hit the wall", 2), ("ran into itself", 1), ("ran out of time", 1)]);
+
+    let odd = Summary::new("one", &[record(1, 5, 10, Ending::FilledBoard)]);
+    assert_eq!(odd.score.median, 5.0);
+    let empty = Summary::new("none", &[]);
+    assert_eq!(empty.games, 0);
+    assert_eq!(empty.score.max, 0.0);
+}
+
+#[test]
+fn formats_are_read_by_name() {
+    assert_eq!("table".parse(), Ok(Format::Table));
+    assert_eq!("CSV".parse(), Ok(Format::Csv));
+    assert_eq!("json".parse(), Ok(Format::Json));
+    assert_eq!(
+        "xml".parse::<Format>(),
+        Err("unknown format `xml` (expected table, csv or json)".to_string())
+    );
+}
+
+#[test]
+fn the_table_gives_every_stat_to_one_decimal() {
+    let autopilot = Summary::new("autopilot", &[record(9, 12, 80, Ending::OutOfTime)]);
+    let table = report(&[summary("greedy"), autopilot], Format::Table);
+    let lines: Vec<&str> = table.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("Bot        Games"), "{}", lines[0]);
+    assert!(lines[0].ends_with("hit the wall  ran into itself  ran out of time"), "{}", lines[0]);
+    assert!(lines[1].starts_with("greedy         4"), "{}", lines[1]);
+    assert!(lines[1].contains(" 4.0/3.0/9.0 "), "{}", lines[1]);
+    assert!(lines[1].contains(" 172.8/80.5/500.0 "), "{}", lines[1]);
+    // Endings an entrant never had still get a column
+    assert!(lines[2].contains(" 12.0/12.0/12.0 "), "{}", lines[2]);
+    assert!(lines[2].ends_with("             0                0                1"), "{}", lines[2]);
+}
+
+#[test]
+fn csv_has_a_column_per_stat_and_ending() {
+    let csv = report(&[summary("bots/greedy --name \"a, b\"")], Format::Csv);
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(
+        lines[0],
+        "bot,games,score_mean,score_median,score_max,length_mean,length_median,length_max,\
+         ticks_mean,ticks_median,ticks_max,hit the wall,ran into itself,ran out of time"
+    );
+    assert_eq!(lines[1], "\"bots/greedy --name \"\"a, b\"\"\",4,4,3,9,5,4,10,172.75,80.5,500,2,1,1");
+}
+
+#[test]
+fn json_lists_the_summaries() {
+    let json = report(&[summary("greedy")], Format::Json);
+    let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed[0]["bot"], "greedy");
+    assert_eq!(parsed[0]["games"], 4);
+    assert_eq!(parsed[0]["score"]["median"], 3.0);
+    assert_eq!(parsed[0]["ticks"]["mean"], 172.75);
+    assert_eq!(parsed[0]["endings"]["hit the wall"], 2);
+}